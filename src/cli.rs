@@ -0,0 +1,170 @@
+use crate::simulation::{
+    integrator, Integrator, HISTORY_EVERY, HISTORY_LENGTH,
+};
+use crate::world::clock::Clock;
+use crate::world::collision::Outcome;
+use crate::world::gravity::Gravity;
+
+pub const USAGE: &str = "\
+Usage: voida [headless --days N [--every SECONDS] [--output FILE]
+                      [--drift FILE]] [OPTIONS]
+       voida --help
+
+Options:
+    --scenario NAME      solar, belt, mercury, earth-moon, sun-earth,
+                         breakup or rings (default: solar)
+    --integrator NAME    euler, verlet, yoshida4, dopri5[:tolerance],
+                         ias15[:epsilon], encke or patched-conics
+                         (default: verlet)
+    --gravity NAME       direct or barnes-hut[:theta], overrides the
+                         scenario's choice
+    --ship-collisions OUTCOME
+                         what happens when a spaceship hits a celestial:
+                         ignore, stop, remove, merge or bounce, overrides
+                         the scenario's choice
+    --celestial-collisions OUTCOME
+                         the same for overlapping celestials
+    --barycentric        move the barycenter to rest at the origin first
+    --rails              move bodies along Kepler conics without
+                         perturbations from 10000 times real time on
+    --load FILE          start from a snapshot instead of the scenario, with
+                         its integrator and delta-t
+    --snapshot FILE      where the S and L keys save and load snapshots
+                         (default: snapshot.json)
+    --history N          states kept for the timeline and rewinding
+                         (default: 1000)
+    --history-every SECONDS
+                         simulated seconds between them (default: 60)
+    --epoch DATE         UTC date of the start like 2024-03-20T03:06:00,
+                         overrides the scenario's choice
+    --delta-t SECONDS    physics step of fixed step integrators
+                         (default: 0.0025)
+    --time-speed X       simulated seconds per second (default: 500)";
+
+pub enum Command {
+    Help,
+    Gui,
+    /// Runs the scenario for `days` without a window and writes the state of
+    /// every body every `every` simulated seconds to `output`, or to stdout,
+    /// and the drift of conserved quantities to `drift`.
+    Headless {
+        days: f64,
+        every: f64,
+        output: Option<String>,
+        drift: Option<String>,
+    },
+}
+
+pub struct Options {
+    pub scenario: String,
+    pub integrator: Box<dyn Integrator>,
+    pub gravity: Option<Gravity>,
+    pub ship_collisions: Option<Outcome>,
+    pub celestial_collisions: Option<Outcome>,
+    pub barycentric: bool,
+    pub rails: bool,
+    pub epoch: Option<Clock>,
+    pub load: Option<String>,
+    pub snapshot: String,
+    pub history: usize,
+    pub history_every: f64,
+    pub delta_t: f64,
+    pub time_speed: f64,
+}
+
+pub fn parse(
+    mut args: impl Iterator<Item = String>,
+) -> Result<(Command, Options), String> {
+    let mut headless = false;
+    let mut days = None;
+    let mut every = 86_400.;
+    let mut output = None;
+    let mut drift = None;
+    let mut options = Options {
+        scenario: "solar".to_string(),
+        integrator: Box::new(integrator::VelocityVerlet),
+        gravity: None,
+        ship_collisions: None,
+        celestial_collisions: None,
+        barycentric: false,
+        rails: false,
+        epoch: None,
+        load: None,
+        snapshot: "snapshot.json".to_string(),
+        history: HISTORY_LENGTH,
+        history_every: HISTORY_EVERY,
+        delta_t: 0.0025,
+        time_speed: 500.,
+    };
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or(format!("Missing value for {}, see --help", arg))
+        };
+        match arg.as_str() {
+            "--help" => return Ok((Command::Help, options)),
+            "headless" => headless = true,
+            "--days" => days = Some(parse_number(&value()?)?),
+            "--every" => every = parse_number(&value()?)?,
+            "--output" => output = Some(value()?),
+            "--drift" => drift = Some(value()?),
+            "--scenario" => options.scenario = value()?,
+            "--integrator" => {
+                let name = value()?;
+                options.integrator = integrator::from_name(&name)
+                    .ok_or(format!("Unknown integrator: {}", name))?;
+            }
+            "--gravity" => {
+                let name = value()?;
+                options.gravity = Some(
+                    Gravity::from_name(&name)
+                        .ok_or(format!("Unknown gravity: {}", name))?,
+                );
+            }
+            "--ship-collisions" => {
+                options.ship_collisions = Some(parse_outcome(&value()?)?);
+            }
+            "--celestial-collisions" => {
+                options.celestial_collisions = Some(parse_outcome(&value()?)?);
+            }
+            "--barycentric" => options.barycentric = true,
+            "--rails" => options.rails = true,
+            "--load" => options.load = Some(value()?),
+            "--snapshot" => options.snapshot = value()?,
+            "--history" => options.history = parse_number(&value()?)? as usize,
+            "--history-every" => {
+                options.history_every = parse_number(&value()?)?;
+            }
+            "--epoch" => options.epoch = Some(Clock::parse(&value()?)?),
+            "--delta-t" => options.delta_t = parse_number(&value()?)?,
+            "--time-speed" => options.time_speed = parse_number(&value()?)?,
+            _ => return Err(format!("Unknown argument {}, see --help", arg)),
+        }
+    }
+
+    let command = if headless {
+        Command::Headless {
+            days: days.ok_or("Missing --days, see --help")?,
+            every,
+            output,
+            drift,
+        }
+    } else {
+        Command::Gui
+    };
+
+    Ok((command, options))
+}
+
+fn parse_number(value: &str) -> Result<f64, String> {
+    match value.parse::<f64>() {
+        Ok(number) if number.is_finite() && number > 0. => Ok(number),
+        _ => Err(format!("Expected a positive number, got {}", value)),
+    }
+}
+
+fn parse_outcome(value: &str) -> Result<Outcome, String> {
+    Outcome::from_name(value)
+        .ok_or(format!("Unknown collision outcome: {}", value))
+}
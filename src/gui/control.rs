@@ -1,4 +1,5 @@
 use crate::utils::Vec3;
+use embedded_graphics::prelude::Point;
 use embedded_graphics_simulator::sdl2::{Keycode, MouseButton};
 use embedded_graphics_simulator::SimulatorEvent;
 use nalgebra::Matrix3;
@@ -7,7 +8,22 @@ use tokio::sync::mpsc;
 pub enum ControlMessage {
     Shutdown,
     Speedup,
+    /// Torque on ISS2 in body coordinates in N m.
+    Torque(Vec3),
+    /// Simulated seconds per second, negative to run backwards.
     SetTimeSpeed(f64),
+    /// Step as fast as the machine allows.
+    MaxSpeed,
+    /// Run time the other way at the same speed.
+    Reverse,
+    /// Go back to the newest state of the history before now and pause.
+    Rewind,
+    /// Go to the state of the history closest to the given time and pause.
+    Jump(f64),
+    /// Write the world to the snapshot file.
+    Save,
+    /// Continue from the snapshot file.
+    Load,
 }
 
 pub struct Shift {
@@ -15,6 +31,57 @@ pub struct Shift {
     pub mouse: Option<(i32, i32)>,
 }
 
+/// Torque about ISS2's body z axis while Z or X is held, in N m.
+const TORQUE: f64 = 1000.;
+
+/// Left end of the timeline bar in display pixels.
+pub const TIMELINE_LEFT: i32 = 10;
+/// Right end of the timeline bar.
+pub const TIMELINE_RIGHT: i32 = 390;
+/// Top of the timeline bar.
+pub const TIMELINE_TOP: i32 = 190;
+pub const TIMELINE_HEIGHT: i32 = 6;
+
+/// Bar along the bottom of the display spanning the history of the
+/// simulation, dragged with the left mouse button to jump through it.
+pub struct Timeline {
+    /// Time of the oldest and the newest state of the history in the last
+    /// world drawn.
+    pub span: Option<(f64, f64)>,
+    dragging: bool,
+}
+
+impl Timeline {
+    /// Time under the display column `x`.
+    pub fn time_at(&self, x: i32) -> Option<f64> {
+        let (oldest, newest) = self.span?;
+        let fraction = (x - TIMELINE_LEFT) as f64
+            / (TIMELINE_RIGHT - TIMELINE_LEFT) as f64;
+        Some(oldest + fraction.clamp(0., 1.) * (newest - oldest))
+    }
+
+    /// Display column of `time`, at the ends for times outside of the
+    /// history.
+    pub fn x_at(&self, time: f64) -> Option<i32> {
+        let (oldest, newest) = self.span?;
+        let fraction = if newest > oldest {
+            ((time - oldest) / (newest - oldest)).clamp(0., 1.)
+        } else {
+            1.
+        };
+        Some(
+            TIMELINE_LEFT
+                + (fraction * (TIMELINE_RIGHT - TIMELINE_LEFT) as f64) as i32,
+        )
+    }
+
+    fn contains(&self, point: Point) -> bool {
+        self.span.is_some()
+            && (TIMELINE_LEFT..=TIMELINE_RIGHT).contains(&point.x)
+            && (TIMELINE_TOP..TIMELINE_TOP + TIMELINE_HEIGHT).contains(&point.y)
+    }
+}
+
 pub enum ControlFlow {
     Continue,
     Break,
@@ -28,6 +95,9 @@ pub struct Control {
     pub camera_extr_inv: Matrix3<f64>,
     pub rmb_coords: (i32, i32),
     pub change_focus: Option<(i32, i32)>,
+    /// Keep the barycenter in the middle instead of a body.
+    pub focus_barycenter: bool,
+    pub timeline: Timeline,
 }
 
 impl Control {
@@ -47,6 +117,11 @@ impl Control {
             camera_extr_inv: Matrix3::identity(),
             rmb_coords: (200, 100),
             change_focus: None,
+            focus_barycenter: false,
+            timeline: Timeline {
+                span: None,
+                dragging: false,
+            },
         }
     }
 
@@ -56,6 +131,13 @@ impl Control {
             .map_err(|e| e.to_string())
     }
 
+    fn jump(&self, x: i32) -> Result<(), String> {
+        match self.timeline.time_at(x) {
+            Some(time) => self.send(ControlMessage::Jump(time)),
+            None => Ok(()),
+        }
+    }
+
     pub fn update(
         &mut self,
         events: impl Iterator<Item = SimulatorEvent>,
@@ -88,8 +170,46 @@ impl Control {
                     Keycode::Num3 => {
                         self.send(ControlMessage::SetTimeSpeed(500.))?;
                     }
+                    Keycode::Num4 => {
+                        self.send(ControlMessage::MaxSpeed)?;
+                    }
+                    Keycode::Num5 => {
+                        self.send(ControlMessage::SetTimeSpeed(86_400.))?;
+                    }
+                    Keycode::Num6 => {
+                        self.send(ControlMessage::SetTimeSpeed(864_000.))?;
+                    }
+                    Keycode::R => {
+                        self.send(ControlMessage::Reverse)?;
+                    }
+                    Keycode::Left => {
+                        self.send(ControlMessage::Rewind)?;
+                    }
+                    Keycode::S => {
+                        self.send(ControlMessage::Save)?;
+                    }
+                    Keycode::L => {
+                        self.send(ControlMessage::Load)?;
+                    }
+                    Keycode::B => {
+                        self.focus_barycenter = !self.focus_barycenter;
+                    }
+                    Keycode::Z | Keycode::X => {
+                        let z = if keycode == Keycode::Z { 1. } else { -1. };
+                        self.send(ControlMessage::Torque(Vec3 {
+                            x: 0.,
+                            y: 0.,
+                            z: z * TORQUE,
+                        }))?;
+                    }
                     _ => {}
                 },
+                SimulatorEvent::KeyUp {
+                    keycode: Keycode::Z | Keycode::X,
+                    ..
+                } => {
+                    self.send(ControlMessage::Torque(Vec3::default()))?;
+                }
                 SimulatorEvent::MouseButtonDown { mouse_btn, point } => {
                     match mouse_btn {
                         MouseButton::Middle => {
@@ -98,18 +218,28 @@ impl Control {
                         MouseButton::Right => {
                             self.rmb_coords = (point.x, point.y);
                         }
+                        MouseButton::Left if self.timeline.contains(point) => {
+                            self.timeline.dragging = true;
+                            self.jump(point.x)?;
+                        }
                         MouseButton::Left => {
                             self.change_focus = Some((point.x, point.y));
+                            self.focus_barycenter = false;
                         }
                         _ => (),
                     }
                 }
                 SimulatorEvent::MouseButtonUp { mouse_btn, .. } => {
-                    if mouse_btn == MouseButton::Middle {
-                        self.shift.mouse = None;
+                    match mouse_btn {
+                        MouseButton::Middle => self.shift.mouse = None,
+                        MouseButton::Left => self.timeline.dragging = false,
+                        _ => (),
                     }
                 }
                 SimulatorEvent::MouseMove { point } => {
+                    if self.timeline.dragging {
+                        self.jump(point.x)?;
+                    }
                     if let Some((mouse_x, mouse_y)) = self.shift.mouse {
                         self.shift.pos += &self.camera_extr_inv
                             * &Vec3 {
@@ -1,14 +1,19 @@
 use crate::gui::control::ControlMessage;
-use crate::gui::control::{Control, ControlFlow};
+use crate::gui::control::{
+    Control, ControlFlow, TIMELINE_HEIGHT, TIMELINE_LEFT, TIMELINE_RIGHT,
+    TIMELINE_TOP,
+};
 use crate::utils::Vec3;
 use crate::world::celestials::Celestial;
+use crate::world::clock;
+use crate::world::particles::Particles;
 use crate::world::spaceship::Spaceship;
 use crate::world::{Body, World};
 use embedded_graphics::geometry::OriginDimensions;
 use embedded_graphics::mono_font::ascii::FONT_5X7;
 use embedded_graphics::mono_font::MonoTextStyle;
-use embedded_graphics::prelude::{DrawTarget, Point, Primitive, Size};
-use embedded_graphics::primitives::{Circle, PrimitiveStyle, Rectangle};
+use embedded_graphics::prelude::{DrawTarget, Pixel, Point, Primitive, Size};
+use embedded_graphics::primitives::{Circle, Line, PrimitiveStyle, Rectangle};
 use embedded_graphics::text::Text;
 use embedded_graphics::{pixelcolor::BinaryColor, Drawable};
 use embedded_graphics_simulator::{
@@ -83,13 +88,16 @@ impl Gui {
 
             let world = self.world_watch.borrow().clone();
             let bodies = world.get_bodies();
+            self.control.timeline.span = world.timeline;
 
-            self.get_focus(&bodies);
+            self.get_focus(&world, &bodies);
 
+            let tt = world.clock.tt(world.time);
+            self.draw_particles(&world.particles);
             for body in bodies.values() {
                 match body {
                     Body::Celestial(c) => {
-                        self.draw_celestial(c);
+                        self.draw_celestial(c, tt);
                     }
                     Body::Spaceship(s) => {
                         self.draw_spaceship(s);
@@ -97,16 +105,28 @@ impl Gui {
                 }
             }
 
+            if let Some(cr3bp) = &world.cr3bp {
+                self.draw_lagrange_points(&cr3bp.lagrange_points());
+            }
+            self.draw_timeline(world.time);
+
             let text_style = MonoTextStyle::new(&FONT_5X7, BinaryColor::On);
             Text::new(
-                &format!("sim fps: {}", world.true_sim_fps),
+                &format!(
+                    "sim fps: {}, x{:.0}",
+                    world.true_sim_fps, world.true_time_speed
+                ),
                 Point::new(2, 6),
                 text_style,
             )
             .draw(&mut self.display)
             .unwrap();
+            let particles = match world.particles.len() {
+                0 => String::new(),
+                count => format!(", {} particles", count),
+            };
             Text::new(
-                &format!("gui fps: {}", fps_reporter),
+                &format!("gui fps: {}{}", fps_reporter, particles),
                 Point::new(2, 13),
                 text_style,
             )
@@ -115,7 +135,7 @@ impl Gui {
             let display_x = self.control.rmb_coords.0 as f64;
             let display_y = self.control.rmb_coords.1 as f64;
             let vec = self.display_to_world(display_x, display_y);
-            let e = bodies.get(&self.focus_name).unwrap().pos();
+            let e = &self.focus;
             Text::new(
                 &format!(
                     "rmb: {} {}, {:.2} {:.2}",
@@ -129,6 +149,98 @@ impl Gui {
             )
             .draw(&mut self.display)
             .unwrap();
+            Text::new(
+                &format!(
+                    "dt: {:.2e} s{}",
+                    world.delta_t,
+                    if world.on_rails { ", on rails" } else { "" }
+                ),
+                Point::new(2, 27),
+                text_style,
+            )
+            .draw(&mut self.display)
+            .unwrap();
+            if let Some(drift) = world.conservation_drift() {
+                Text::new(
+                    &format!(
+                        "drift E {:.1e} p {:.1e} L {:.1e} cm {:.1e} m",
+                        drift.energy,
+                        drift.momentum,
+                        drift.angular_momentum,
+                        drift.barycenter,
+                    ),
+                    Point::new(2, 34),
+                    text_style,
+                )
+                .draw(&mut self.display)
+                .unwrap();
+            }
+            Text::new(
+                &format!(
+                    "{} JD {:.5} TDB",
+                    world.clock.date(world.time),
+                    clock::julian_date(world.clock.tdb(world.time))
+                ),
+                Point::new(2, 41),
+                text_style,
+            )
+            .draw(&mut self.display)
+            .unwrap();
+            let mut line = 48;
+            let focused = world.spaceships.get(&self.focus_name);
+            if let (Some(cr3bp), Some(ship)) = (&world.cr3bp, focused) {
+                Text::new(
+                    &format!(
+                        "jacobi {}-{}: {:.6e} m2/s2",
+                        cr3bp.primary,
+                        cr3bp.secondary,
+                        cr3bp.jacobi(&ship.pos(), &ship.vel())
+                    ),
+                    Point::new(2, line),
+                    text_style,
+                )
+                .draw(&mut self.display)
+                .unwrap();
+                line += 7;
+            }
+            if let Some(ship) = focused {
+                let spheres = world.celestials.spheres_of_influence();
+                let dominant = world.celestials.dominant(&spheres, &ship.pos());
+                if let Some(celestial) =
+                    dominant.and_then(|d| world.celestials.get_ref(&d))
+                {
+                    Text::new(
+                        &format!(
+                            "{} over {}: {}",
+                            ship.name(),
+                            celestial.name(),
+                            celestial.geodetic(&ship.pos(), tt)
+                        ),
+                        Point::new(2, line),
+                        text_style,
+                    )
+                    .draw(&mut self.display)
+                    .unwrap();
+                    line += 7;
+                }
+            }
+            if let Some(switch) = world.soi_switches.last() {
+                Text::new(&switch.to_string(), Point::new(2, line), text_style)
+                    .draw(&mut self.display)
+                    .unwrap();
+                line += 7;
+            }
+            for (i, collision) in
+                world.collisions.iter().rev().take(3).enumerate()
+            {
+                Text::new(
+                    &collision.to_string(),
+                    Point::new(2, line + 7 * i as i32),
+                    text_style,
+                )
+                .draw(&mut self.display)
+                .unwrap();
+            }
 
             window.update(&self.display);
         }
@@ -166,7 +278,12 @@ impl Gui {
         (x_display, y_display)
     }
 
-    fn get_focus(&mut self, bodies: &HashMap<String, Body>) {
+    fn get_focus(&mut self, world: &World, bodies: &HashMap<String, Body>) {
+        if self.control.focus_barycenter {
+            self.focus = world.barycenter().0;
+            return;
+        }
+
         if let Some((display_x, display_y)) = self.control.change_focus {
             let click =
                 self.display_to_world(display_x as f64, display_y as f64);
@@ -183,10 +300,20 @@ impl Gui {
             }
         }
 
-        self.focus = bodies.get(&self.focus_name).unwrap().pos();
+        // The focused body may have been removed by a collision.
+        if !bodies.contains_key(&self.focus_name) {
+            if let Some(name) = bodies.keys().min() {
+                self.focus_name.clone_from(name);
+            }
+        }
+        if let Some(body) = bodies.get(&self.focus_name) {
+            self.focus = body.pos();
+        }
     }
 
-    fn draw_celestial(&mut self, c: &Celestial) {
+    /// A circle, with a line out to where the prime meridian crosses the
+    /// equator on rotating celestials at `tt`.
+    fn draw_celestial(&mut self, c: &Celestial, tt: f64) {
         let size = self.display.size();
         let (width, height) = (size.width as f64, size.height as f64);
         let (x, y) = self.world_to_display(&c.pos());
@@ -212,19 +339,130 @@ impl Gui {
             .into_styled(line_style)
             .draw(&mut self.display)
             .unwrap();
+
+        if c.rotation().is_some() && rad >= 2. {
+            let meridian = Vec3 {
+                x: c.rad(),
+                y: 0.,
+                z: 0.,
+            };
+            let (pos, _) = c.to_inertial(&meridian, &Vec3::default(), tt);
+            let (end_x, end_y) = self.world_to_display(&pos);
+            Line::new(
+                Point::new(x as i32, y as i32),
+                Point::new(end_x as i32, end_y as i32),
+            )
+            .into_styled(line_style)
+            .draw(&mut self.display)
+            .unwrap();
+        }
     }
 
-    fn draw_spaceship(&mut self, s: &Spaceship) {
-        let line_style = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
+    /// Every particle as a single pixel.
+    fn draw_particles(&mut self, particles: &Particles) {
+        let size = self.display.size();
+        for i in 0..particles.len() {
+            let (x, y) = self.world_to_display(&particles.pos(i));
+            if x >= 0.
+                && y >= 0.
+                && x < size.width as f64
+                && y < size.height as f64
+            {
+                Pixel(Point::new(x as i32, y as i32), BinaryColor::On)
+                    .draw(&mut self.display)
+                    .unwrap();
+            }
+        }
+    }
 
-        let (x, y) = self.world_to_display(&s.pos());
+    fn draw_lagrange_points(&mut self, points: &[Vec3; 5]) {
+        let text_style = MonoTextStyle::new(&FONT_5X7, BinaryColor::On);
+        for (i, point) in points.iter().enumerate() {
+            let (x, y) = self.world_to_display(point);
+            Text::new(
+                &format!("L{}", i + 1),
+                Point::new(x as i32 - 4, y as i32 + 3),
+                text_style,
+            )
+            .draw(&mut self.display)
+            .unwrap();
+        }
+    }
+
+    /// The stored history as a bar with a mark at `time`.
+    fn draw_timeline(&mut self, time: f64) {
+        if self.control.timeline.span.is_none() {
+            return;
+        }
+        let line_style = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
         Rectangle::new(
-            Point::new(x as i32 - 5, y as i32 - 5),
-            Size::new(10, 10),
+            Point::new(TIMELINE_LEFT, TIMELINE_TOP),
+            Size::new(
+                (TIMELINE_RIGHT - TIMELINE_LEFT) as u32 + 1,
+                TIMELINE_HEIGHT as u32,
+            ),
         )
         .into_styled(line_style)
         .draw(&mut self.display)
         .unwrap();
+
+        if let Some(x) = self.control.timeline.x_at(time) {
+            let fill_style = PrimitiveStyle::with_fill(BinaryColor::On);
+            Rectangle::new(
+                Point::new(x - 1, TIMELINE_TOP - 2),
+                Size::new(3, TIMELINE_HEIGHT as u32 + 4),
+            )
+            .into_styled(fill_style)
+            .draw(&mut self.display)
+            .unwrap();
+        }
+    }
+
+    /// A square in the spaceship's body x-y plane, turned as the spaceship
+    /// is and seen by the camera, with a line out of the body x side.
+    fn draw_spaceship(&mut self, s: &Spaceship) {
+        let line_style = PrimitiveStyle::with_stroke(BinaryColor::On, 1);
+
+        let (x, y) = self.world_to_display(&s.pos());
+        let orientation = &s.attitude().orientation;
+        let axis = |axis: Vec3| {
+            let frame =
+                &self.control.camera_extr * &orientation.rotate(&axis) * 5.;
+            (frame.x, -frame.y)
+        };
+        let (fx, fy) = axis(Vec3 {
+            x: 1.,
+            y: 0.,
+            z: 0.,
+        });
+        let (sx, sy) = axis(Vec3 {
+            x: 0.,
+            y: 1.,
+            z: 0.,
+        });
+        let point = |a: f64, b: f64| {
+            Point::new(
+                (x + a * fx + b * sx) as i32,
+                (y + a * fy + b * sy) as i32,
+            )
+        };
+
+        let corners = [
+            point(1., 1.),
+            point(1., -1.),
+            point(-1., -1.),
+            point(-1., 1.),
+        ];
+        for (i, corner) in corners.iter().enumerate() {
+            Line::new(*corner, corners[(i + 1) % 4])
+                .into_styled(line_style)
+                .draw(&mut self.display)
+                .unwrap();
+        }
+        Line::new(point(1., 0.), point(2., 0.))
+            .into_styled(line_style)
+            .draw(&mut self.display)
+            .unwrap();
     }
 }
 
@@ -0,0 +1,94 @@
+use crate::simulation::Simulation;
+use crate::world::diagnostics::Diagnostics;
+use crate::world::World;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+
+/// Runs `simulation` for `days` simulated days and writes the state of every
+/// body as CSV, with the UTC date, every `every` simulated seconds, to
+/// `output` or to stdout. Collisions and sphere of influence switches are
+/// reported on stderr. With `drift` the drift of conserved quantities since
+/// the start goes to that file at the same times.
+pub fn run(
+    simulation: &mut Simulation,
+    days: f64,
+    every: f64,
+    output: Option<String>,
+    drift: Option<String>,
+) -> Result<(), String> {
+    let mut output: Box<dyn Write> = match output {
+        Some(path) => Box::new(create(&path)?),
+        None => Box::new(BufWriter::new(io::stdout().lock())),
+    };
+    let mut drift = drift.map(|path| create(&path)).transpose()?;
+
+    writeln!(output, "time,date,body,x,y,z,vx,vy,vz")
+        .map_err(|e| e.to_string())?;
+    if let Some(drift) = &mut drift {
+        writeln!(drift, "time,energy,momentum,angular_momentum,barycenter")
+            .map_err(|e| e.to_string())?;
+    }
+    let mut reported = f64::NEG_INFINITY;
+    simulation.run_until(days * 86_400., every, |world| {
+        for collision in &world.collisions {
+            if collision.time > reported {
+                eprintln!("{}", collision);
+            }
+        }
+        for switch in &world.soi_switches {
+            if switch.time > reported {
+                eprintln!("{}", switch);
+            }
+        }
+        reported = world.time;
+        if let Some(drift) = &mut drift {
+            write_drift(drift, world)?;
+        }
+        write_state(&mut output, world)
+    })?;
+    if let Some(drift) = &mut drift {
+        drift.flush().map_err(|e| e.to_string())?;
+    }
+    output.flush().map_err(|e| e.to_string())
+}
+
+fn create(path: &str) -> Result<BufWriter<File>, String> {
+    Ok(BufWriter::new(
+        File::create(path).map_err(|e| format!("{}: {}", path, e))?,
+    ))
+}
+
+fn write_drift(output: &mut impl Write, world: &World) -> Result<(), String> {
+    let Some(start) = &world.initial_diagnostics else {
+        return Ok(());
+    };
+    let drift = Diagnostics::new(world).drift(start);
+    writeln!(
+        output,
+        "{},{},{},{},{}",
+        world.time,
+        drift.energy,
+        drift.momentum,
+        drift.angular_momentum,
+        drift.barycenter,
+    )
+    .map_err(|e| e.to_string())
+}
+
+fn write_state(output: &mut impl Write, world: &World) -> Result<(), String> {
+    let bodies = world.get_bodies();
+    let mut names: Vec<_> = bodies.keys().collect();
+    names.sort();
+
+    let date = world.clock.date(world.time);
+    for name in names {
+        let (pos, vel) = (bodies[name].pos(), bodies[name].vel());
+        writeln!(
+            output,
+            "{},{},{},{},{},{},{},{},{}",
+            world.time, date, name, pos.x, pos.y, pos.z, vel.x, vel.y, vel.z,
+        )
+        .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
@@ -1,11 +1,14 @@
+mod cli;
 mod gui;
+mod headless;
 mod simulation;
 mod utils;
 mod world;
 
+use cli::Command;
 use gui::Gui;
+use simulation::snapshot::Snapshot;
 use simulation::Simulation;
-use std::collections::HashMap;
 use std::thread;
 use tokio::sync::mpsc;
 use utils::Vec3;
@@ -14,25 +17,64 @@ use world::{config, World};
 
 #[tokio::main]
 async fn main() -> Result<(), String> {
-    let celestials = config::new_solar();
-    let mut spaceships = HashMap::new();
-    let spaceship = config::iss();
-    let spaceship2 = config::iss2();
-    spaceships.insert(spaceship.name(), spaceship);
-    spaceships.insert(spaceship2.name(), spaceship2);
-    let world = World::new(celestials, spaceships);
+    let (command, options) = cli::parse(std::env::args().skip(1))?;
+    let (mut world, integrator, delta_t) = match &options.load {
+        Some(path) => {
+            let snapshot = Snapshot::load(path)?;
+            let integrator = snapshot.integrator()?;
+            (snapshot.world, integrator, snapshot.delta_t)
+        }
+        None => (
+            config::scenario(&options.scenario)
+                .ok_or(format!("Unknown scenario: {}", options.scenario))?,
+            options.integrator,
+            options.delta_t,
+        ),
+    };
+    if let Some(gravity) = options.gravity {
+        world.gravity = gravity;
+    }
+    if let Some(outcome) = options.ship_collisions {
+        world.collision_policy.spaceships = outcome;
+    }
+    if let Some(outcome) = options.celestial_collisions {
+        world.collision_policy.celestials = outcome;
+    }
+    world.barycentric |= options.barycentric;
+    if let Some(clock) = options.epoch {
+        world.clock = clock;
+    }
     let (control_sender, control_receiver) = mpsc::channel(100);
 
-    let simulation_fps = 200_000;
-    let time_speed = 500.;
+    let (mut simulation, world_watch) = Simulation::new(
+        world,
+        delta_t,
+        options.time_speed,
+        integrator,
+        control_receiver,
+    );
+    simulation.set_snapshot_path(options.snapshot);
+    simulation.set_rails(options.rails);
+    simulation.set_history(options.history, options.history_every);
 
-    let (mut simulation, world_watch) =
-        Simulation::new(world, simulation_fps, time_speed, control_receiver);
+    match command {
+        Command::Help => {
+            println!("{}", cli::USAGE);
+            Ok(())
+        }
+        Command::Gui => {
+            let gui = Gui::new(20., world_watch, control_sender);
+            let gui_handle = thread::spawn(move || gui.run());
 
-    let gui = Gui::new(20., world_watch, control_sender);
-    let gui_handle = thread::spawn(move || gui.run());
+            simulation.spin().await?;
 
-    simulation.spin().await?;
-
-    gui_handle.join().unwrap()
+            gui_handle.join().unwrap()
+        }
+        Command::Headless {
+            days,
+            every,
+            output,
+            drift,
+        } => headless::run(&mut simulation, days, every, output, drift),
+    }
 }
@@ -0,0 +1,350 @@
+use super::integrator::{Integrator, Yoshida4};
+use crate::utils::{Vec3, G};
+use crate::world::kepler;
+use crate::world::spaceship::Spaceship;
+use crate::world::World;
+use std::collections::HashMap;
+
+/// Deviation from the conic, relative to the distance from the reference,
+/// beyond which the conic is rectified to the current state.
+const RECTIFY: f64 = 1e-2;
+
+/// Encke's method. Celestials are integrated as usual, while every spaceship
+/// is kept as a Kepler conic around the celestial whose sphere of influence
+/// it is in plus a small deviation from that conic, which is integrated
+/// kick-drift-kick. Everything the spaceship feels besides its reference
+/// celestial's point mass pull drives the deviation. The state lives in
+/// coordinates relative to the reference celestial and only the sum is
+/// written back to the heliocentric [`World`], so the rounding of large
+/// coordinates does not pile up step after step. The conic is rectified
+/// to the current state once the deviation grows, when the spaceship
+/// changes sphere of influence or when anything else moved it.
+#[derive(Default)]
+pub struct Encke {
+    /// Conic and deviation of each spaceship.
+    orbits: HashMap<String, Orbit>,
+}
+
+struct Orbit {
+    /// Celestial the conic is around, none for a straight line, as in CR3BP
+    /// mode or without celestials.
+    reference: Option<String>,
+    mu: f64,
+    /// Osculating state relative to the reference when the conic was
+    /// rectified.
+    pos: Vec3,
+    vel: Vec3,
+    /// Time on the conic since then.
+    elapsed: f64,
+    /// State relative to the conic.
+    deviation: Vec3,
+    deviation_vel: Vec3,
+    /// Acceleration of the deviation at the current state, none until it
+    /// is known.
+    acceleration: Option<Vec3>,
+    /// State last written to the spaceship, to tell when anything else
+    /// moved it.
+    written: (Vec3, Vec3),
+}
+
+impl Orbit {
+    /// Conic through the state of `spaceship` around the celestial whose
+    /// sphere of influence it is in.
+    fn osculating(world: &World, spaceship: &Spaceship) -> Self {
+        let reference = if world.cr3bp.is_some() {
+            None
+        } else {
+            let spheres = world.celestials.spheres_of_influence();
+            world.celestials.dominant(&spheres, &spaceship.pos())
+        };
+        let mu = reference
+            .as_ref()
+            .and_then(|r| world.celestials.get_ref(r))
+            .map_or(0., |c| G * c.mass());
+        let (pos, vel) = frame(world, &reference);
+        Self {
+            reference,
+            mu,
+            pos: spaceship.pos() - &pos,
+            vel: spaceship.vel() - &vel,
+            elapsed: 0.,
+            deviation: Vec3::default(),
+            deviation_vel: Vec3::default(),
+            acceleration: None,
+            written: (spaceship.pos(), spaceship.vel()),
+        }
+    }
+
+    /// Position and velocity on the conic relative to the reference.
+    fn conic(&self) -> (Vec3, Vec3) {
+        if self.mu == 0. {
+            return (&self.pos + &self.vel * self.elapsed, self.vel.clone());
+        }
+        kepler::propagate(self.mu, &self.pos, &self.vel, self.elapsed)
+    }
+
+    /// Acceleration of the deviation from the accelerations of the
+    /// spaceship and its reference, when the conic is at `conic`.
+    fn acceleration(
+        &self,
+        spaceship: &Vec3,
+        reference: &Vec3,
+        conic: &Vec3,
+    ) -> Vec3 {
+        let distance = conic.normalize().distance;
+        let two_body = if self.mu == 0. {
+            Vec3::default()
+        } else {
+            conic * (self.mu / distance.powi(3))
+        };
+        spaceship - reference + &two_body
+    }
+
+    fn moved(&self, spaceship: &Spaceship) -> bool {
+        !spaceship.pos().equal_to(&self.written.0, 0.)
+            || !spaceship.vel().equal_to(&self.written.1, 0.)
+    }
+
+    /// Whether the reference is gone, for example merged into another
+    /// celestial.
+    fn orphaned(&self, world: &World) -> bool {
+        self.reference
+            .as_ref()
+            .is_some_and(|r| world.celestials.get_ref(r).is_none())
+    }
+}
+
+/// Position and velocity of `reference`, the origin for none.
+fn frame(world: &World, reference: &Option<String>) -> (Vec3, Vec3) {
+    reference
+        .as_ref()
+        .and_then(|r| world.celestials.get_ref(r))
+        .map_or((Vec3::default(), Vec3::default()), |c| (c.pos(), c.vel()))
+}
+
+impl Encke {
+    /// Fills in the acceleration of every orbit's deviation in the current
+    /// state of the world.
+    fn accelerate(&mut self, world: &World, conics: &HashMap<String, Vec3>) {
+        let accelerations = world.accelerations();
+        let names = world
+            .celestials
+            .values()
+            .map(|c| c.name())
+            .chain(world.spaceships.keys().cloned());
+        let accelerations: HashMap<String, Vec3> =
+            names.zip(accelerations).collect();
+
+        for (name, orbit) in &mut self.orbits {
+            let reference = orbit
+                .reference
+                .as_ref()
+                .map_or(Vec3::default(), |r| accelerations[r].clone());
+            orbit.acceleration = Some(orbit.acceleration(
+                &accelerations[name],
+                &reference,
+                &conics[name],
+            ));
+        }
+    }
+}
+
+impl Integrator for Encke {
+    fn step(&mut self, world: &mut World, delta_t: f64) -> f64 {
+        self.orbits
+            .retain(|name, _| world.spaceships.contains_key(name));
+        for (name, spaceship) in &world.spaceships {
+            if self
+                .orbits
+                .get(name)
+                .is_none_or(|o| o.moved(spaceship) || o.orphaned(world))
+            {
+                let orbit = Orbit::osculating(world, spaceship);
+                self.orbits.insert(name.clone(), orbit);
+            }
+        }
+        if self.orbits.values().any(|o| o.acceleration.is_none()) {
+            let conics = self
+                .orbits
+                .iter()
+                .map(|(name, orbit)| (name.clone(), orbit.conic().0))
+                .collect();
+            self.accelerate(world, &conics);
+        }
+
+        for orbit in self.orbits.values_mut() {
+            let acceleration = orbit.acceleration.take().unwrap();
+            orbit.deviation_vel += acceleration * (delta_t / 2.);
+            orbit.deviation += &orbit.deviation_vel * delta_t;
+            orbit.elapsed += delta_t;
+        }
+
+        let spaceships = std::mem::take(&mut world.spaceships);
+        Yoshida4.step(world, delta_t);
+        world.spaceships = spaceships;
+
+        let mut conics = HashMap::new();
+        for (name, orbit) in &self.orbits {
+            let (pos, vel) = orbit.conic();
+            let (origin, drift) = frame(world, &orbit.reference);
+            // Summed up relative to the reference first, where the
+            // coordinates are small.
+            world.spaceships.get_mut(name).unwrap().set_state(
+                origin + &(pos.clone() + &orbit.deviation),
+                drift + &(vel.clone() + &orbit.deviation_vel),
+            );
+            conics.insert(name.clone(), (pos, vel));
+        }
+        let positions = conics
+            .iter()
+            .map(|(name, (pos, _))| (name.clone(), pos.clone()))
+            .collect();
+        self.accelerate(world, &positions);
+
+        let mut stale = Vec::new();
+        let spheres = world.celestials.spheres_of_influence();
+        for (name, orbit) in &mut self.orbits {
+            let acceleration = orbit.acceleration.as_ref().unwrap();
+            orbit.deviation_vel += acceleration * (delta_t / 2.);
+
+            let (pos, vel) = &conics[name];
+            let drift = frame(world, &orbit.reference).1;
+            let spaceship = world.spaceships.get_mut(name).unwrap();
+            spaceship.set_state(
+                spaceship.pos(),
+                drift + &(vel.clone() + &orbit.deviation_vel),
+            );
+            orbit.written = (spaceship.pos(), spaceship.vel());
+
+            let dominant = match world.cr3bp {
+                Some(_) => None,
+                None => world.celestials.dominant(&spheres, &spaceship.pos()),
+            };
+            let distance = pos.normalize().distance;
+            if dominant != orbit.reference
+                || orbit.deviation.normalize().distance > RECTIFY * distance
+            {
+                stale.push(name.clone());
+            }
+        }
+        for name in stale {
+            let orbit = Orbit::osculating(world, &world.spaceships[&name]);
+            self.orbits.insert(name, orbit);
+        }
+        delta_t
+    }
+
+    fn name(&self) -> String {
+        "encke".to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::celestials::{Celestial, Celestials};
+    use crate::world::config;
+    use std::collections::BTreeMap;
+
+    /// The given celestials as point masses and a spaceship in a circular
+    /// orbit 7000 km from the Earth's center, and the orbit's period.
+    fn world(celestials: &[Celestial]) -> (World, f64) {
+        let mut point_masses = Celestials::new();
+        for c in celestials {
+            point_masses.add(Celestial::new(
+                c.name(),
+                c.mass(),
+                c.pos(),
+                c.vel(),
+                c.rad(),
+            ));
+        }
+        let earth = config::earth();
+        let mu = G * earth.mass();
+        let radius = 7e6;
+        let spaceship = Spaceship::new(
+            "Probe".to_string(),
+            1000.,
+            earth.pos()
+                + &Vec3 {
+                    x: radius,
+                    y: 0.,
+                    z: 0.,
+                },
+            earth.vel()
+                + &Vec3 {
+                    x: 0.,
+                    y: (mu / radius).sqrt(),
+                    z: 0.,
+                },
+        );
+        let mut spaceships = BTreeMap::new();
+        spaceships.insert(spaceship.name(), spaceship);
+        let period = std::f64::consts::TAU * (radius.powi(3) / mu).sqrt();
+        (World::new(point_masses, spaceships), period)
+    }
+
+    fn run(
+        world: &mut World,
+        integrator: &mut dyn Integrator,
+        duration: f64,
+        steps: usize,
+    ) {
+        for _ in 0..steps {
+            integrator.step(world, duration / steps as f64);
+        }
+    }
+
+    /// Position of the spaceship relative to the Earth.
+    fn relative(world: &World) -> Vec3 {
+        world.spaceships["Probe"].pos()
+            - &world.celestials.get_ref("Earth").unwrap().pos()
+    }
+
+    #[test]
+    fn test_holds_a_conic_far_from_the_origin() {
+        let (start, period) = world(&[config::earth()]);
+        let (exact, _) = kepler::propagate(
+            G * config::earth().mass(),
+            &relative(&start),
+            &(start.spaceships["Probe"].vel() - &config::earth().vel()),
+            period,
+        );
+
+        let mut encke = start.clone();
+        run(&mut encke, &mut Encke::default(), period, 6000);
+        let mut yoshida = start;
+        run(&mut yoshida, &mut Yoshida4, period, 6000);
+
+        let error =
+            |world: &World| (relative(world) - &exact).normalize().distance;
+        // Heliocentric coordinates cost Yoshida4 millimetres in one orbit.
+        assert!(error(&encke) < 1e-4, "{}", error(&encke));
+        assert!(error(&encke) * 100. < error(&yoshida));
+    }
+
+    #[test]
+    fn test_matches_n_body_with_perturbations() {
+        let (start, period) =
+            world(&[config::sun(), config::earth(), config::moon()]);
+        let mut encke = start.clone();
+        run(&mut encke, &mut Encke::default(), period, 600);
+        let mut yoshida = start;
+        run(&mut yoshida, &mut Yoshida4, period, 6000);
+
+        let error = (relative(&encke) - &relative(&yoshida))
+            .normalize()
+            .distance;
+        assert!(error < 1., "{error}");
+    }
+
+    #[test]
+    fn test_survives_losing_its_reference() {
+        let (mut world, period) = world(&[config::sun(), config::earth()]);
+        let mut encke = Encke::default();
+        run(&mut world, &mut encke, period / 100., 10);
+        world.celestials.remove("Earth");
+        run(&mut world, &mut encke, period / 100., 10);
+        assert_eq!(encke.orbits["Probe"].reference, Some("Sun".to_string()));
+    }
+}
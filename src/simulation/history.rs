@@ -0,0 +1,109 @@
+use crate::World;
+use std::collections::VecDeque;
+
+/// Particles in all stored states together at most, as large ensembles
+/// would otherwise fill the memory.
+const MAX_PARTICLES: usize = 2_000_000;
+
+/// Ring buffer of past states of the world, at least `every` simulated
+/// seconds apart, oldest first. When it is full, or would hold more than
+/// `MAX_PARTICLES` particles, the oldest states make room for the newest.
+pub struct History {
+    worlds: VecDeque<World>,
+    length: usize,
+    every: f64,
+}
+
+impl History {
+    pub fn new(length: usize, every: f64) -> Self {
+        Self {
+            worlds: VecDeque::with_capacity(length),
+            length,
+            every,
+        }
+    }
+
+    /// Keeps `world` if it lies `every` seconds after the newest state.
+    /// States after `world` belong to a future that is being rewritten, for
+    /// example after a jump back or while running backwards, and are
+    /// dropped first.
+    pub fn record(&mut self, world: &World) {
+        while self.worlds.back().is_some_and(|w| w.time > world.time) {
+            self.worlds.pop_back();
+        }
+        if self
+            .worlds
+            .back()
+            .is_some_and(|w| world.time - w.time < self.every)
+        {
+            return;
+        }
+        let particles = |worlds: &VecDeque<World>| -> usize {
+            worlds.iter().map(|w| w.particles.len()).sum()
+        };
+        while !self.worlds.is_empty()
+            && (self.worlds.len() >= self.length
+                || particles(&self.worlds) + world.particles.len()
+                    > MAX_PARTICLES)
+        {
+            self.worlds.pop_front();
+        }
+        if self.length > 0 {
+            self.worlds.push_back(world.clone());
+        }
+    }
+
+    /// The stored state closest to `time`.
+    pub fn nearest(&self, time: f64) -> Option<&World> {
+        self.worlds.iter().min_by(|a, b| {
+            (a.time - time).abs().total_cmp(&(b.time - time).abs())
+        })
+    }
+
+    /// The newest stored state before `time`.
+    pub fn before(&self, time: f64) -> Option<&World> {
+        self.worlds.iter().rev().find(|w| w.time < time)
+    }
+
+    /// Time of the oldest and the newest stored state.
+    pub fn span(&self) -> Option<(f64, f64)> {
+        Some((self.worlds.front()?.time, self.worlds.back()?.time))
+    }
+
+    pub fn clear(&mut self) {
+        self.worlds.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::celestials::Celestials;
+
+    fn at(time: f64) -> World {
+        let mut world = World::new(Celestials::new(), Default::default());
+        world.time = time;
+        world
+    }
+
+    #[test]
+    fn test_decimates_and_rewrites_the_future() {
+        let mut history = History::new(4, 10.);
+        for second in 0..100 {
+            history.record(&at(second as f64));
+        }
+        assert_eq!(history.span(), Some((60., 90.)));
+        assert_eq!(history.nearest(74.).unwrap().time, 70.);
+        assert_eq!(history.nearest(1000.).unwrap().time, 90.);
+        assert_eq!(history.before(70.).unwrap().time, 60.);
+        assert!(history.before(60.).is_none());
+
+        // Resuming from 70 s drops what came after it.
+        history.record(&at(70.));
+        assert_eq!(history.span(), Some((60., 70.)));
+        history.record(&at(75.));
+        assert_eq!(history.span(), Some((60., 70.)));
+        history.record(&at(80.));
+        assert_eq!(history.span(), Some((60., 80.)));
+    }
+}
@@ -0,0 +1,352 @@
+use super::integrator::INITIAL_STEP;
+use super::Integrator;
+use crate::utils::Vec3;
+use crate::world::{State, World};
+
+/// Gauss-Radau spacings of the substeps within one step.
+const SPACINGS: [f64; 8] = [
+    0.,
+    0.056_262_560_536_922_15,
+    0.180_240_691_736_892_36,
+    0.352_624_717_113_169_6,
+    0.547_153_626_330_555_4,
+    0.734_210_177_215_410_5,
+    0.885_320_946_839_095_8,
+    0.977_520_613_561_287_5,
+];
+const SAFETY_FACTOR: f64 = 0.25;
+const MAX_ITERATIONS: usize = 12;
+
+/// IAS15, the 15th order adaptive Gauss-Radau scheme by Rein and Spiegel.
+/// The acceleration of every coordinate within a step is approximated by a
+/// 7th degree polynomial, `a(τ) = a0 + b[0] τ + ... + b[6] τ^7`, which is
+/// refined with a predictor-corrector loop. The size of `b[6]` relative to
+/// the acceleration drives the step size.
+pub struct Ias15 {
+    epsilon: f64,
+    next_step: Option<f64>,
+    /// `newton[k][j]` is the coefficient of `τ^(j+1)` in
+    /// `τ (τ - h1) ... (τ - hk)`, which converts `g` to `b`.
+    newton: [[f64; 7]; 7],
+    last: Option<LastStep>,
+}
+
+/// Converged `b` coefficients of the last accepted step and its size.
+struct LastStep {
+    b: Vec<[f64; 7]>,
+    h: f64,
+}
+
+impl Ias15 {
+    pub const DEFAULT_EPSILON: f64 = 1e-9;
+
+    pub fn new(epsilon: f64) -> Self {
+        let mut newton = [[0.; 7]; 7];
+        let mut poly = vec![0., 1.];
+        for (k, row) in newton.iter_mut().enumerate() {
+            row[..k + 1].copy_from_slice(&poly[1..]);
+            let mut next = vec![0.; poly.len() + 1];
+            for (power, coeff) in poly.iter().enumerate() {
+                next[power + 1] += coeff;
+                next[power] -= coeff * SPACINGS[k + 1];
+            }
+            poly = next;
+        }
+
+        Self {
+            epsilon,
+            next_step: None,
+            newton,
+            last: None,
+        }
+    }
+
+    fn flatten(vectors: &[Vec3]) -> Vec<f64> {
+        vectors.iter().flat_map(|v| [v.x, v.y, v.z]).collect()
+    }
+
+    fn unflatten(components: &[f64]) -> Vec<Vec3> {
+        components
+            .chunks(3)
+            .map(|c| Vec3 {
+                x: c[0],
+                y: c[1],
+                z: c[2],
+            })
+            .collect()
+    }
+
+    fn accelerations(world: &mut World, x: &[f64], v: &[f64]) -> Vec<f64> {
+        world.set_state(&State {
+            pos: Self::unflatten(x),
+            vel: Self::unflatten(v),
+        });
+        Self::flatten(&world.accelerations())
+    }
+
+    /// Shifts the polynomial of the last accepted step to a new step of size
+    /// `h`, or starts from zero if there is nothing to extrapolate from. A
+    /// step much longer than the last one, like after a step that was cut
+    /// short by `delta_t`, would only amplify noise, and so would one in the
+    /// other direction of time.
+    fn predict(&self, len: usize, h: f64) -> Vec<[f64; 7]> {
+        let last = match &self.last {
+            Some(last)
+                if last.b.len() == len
+                    && h.signum() == last.h.signum()
+                    && h.abs() <= last.h.abs() / SAFETY_FACTOR =>
+            {
+                last
+            }
+            _ => return vec![[0.; 7]; len],
+        };
+        let q = h / last.h;
+
+        last.b
+            .iter()
+            .map(|b| {
+                let mut predicted = [0.; 7];
+                for (m, p) in predicted.iter_mut().enumerate() {
+                    let m = m + 1;
+                    let mut sum = 0.;
+                    let mut binomial = 1.;
+                    for k in m..=7 {
+                        sum += binomial * b[k - 1];
+                        binomial *= (k + 1) as f64 / (k + 1 - m) as f64;
+                    }
+                    *p = q.powi(m as i32) * sum;
+                }
+                predicted
+            })
+            .collect()
+    }
+
+    fn b_to_g(&self, b: &[f64; 7]) -> [f64; 7] {
+        let mut g = [0.; 7];
+        for k in (0..7).rev() {
+            let mut value = b[k];
+            for (g, newton) in g.iter().zip(&self.newton).skip(k + 1) {
+                value -= g * newton[k];
+            }
+            g[k] = value;
+        }
+        g
+    }
+
+    fn position(
+        x0: f64,
+        v0: f64,
+        a0: f64,
+        b: &[f64; 7],
+        h: f64,
+        tau: f64,
+    ) -> f64 {
+        let mut sum = a0 / 2.;
+        for (j, b) in b.iter().enumerate() {
+            sum += b * tau.powi(j as i32 + 1) / ((j + 2) * (j + 3)) as f64;
+        }
+        x0 + h * tau * (v0 + h * tau * sum)
+    }
+
+    fn velocity(v0: f64, a0: f64, b: &[f64; 7], h: f64, tau: f64) -> f64 {
+        let mut sum = a0;
+        for (j, b) in b.iter().enumerate() {
+            sum += b * tau.powi(j as i32 + 1) / (j + 2) as f64;
+        }
+        v0 + h * tau * sum
+    }
+
+    /// Runs the predictor-corrector loop for a step of size `h`, starting
+    /// from the predicted coefficients, and returns the converged ones.
+    fn iterate(
+        &self,
+        world: &mut World,
+        (x0, v0, a0): (&[f64], &[f64], &[f64]),
+        h: f64,
+        mut b: Vec<[f64; 7]>,
+    ) -> Vec<[f64; 7]> {
+        let len = x0.len();
+        let mut g: Vec<[f64; 7]> = b.iter().map(|b| self.b_to_g(b)).collect();
+
+        let mut last_error = f64::MAX;
+        for iteration in 0..MAX_ITERATIONS {
+            let mut changes = vec![0.; len];
+            let mut a = Vec::new();
+
+            for n in 1..8 {
+                let tau = SPACINGS[n];
+                let x: Vec<f64> = (0..len)
+                    .map(|i| Self::position(x0[i], v0[i], a0[i], &b[i], h, tau))
+                    .collect();
+                let v: Vec<f64> = (0..len)
+                    .map(|i| Self::velocity(v0[i], a0[i], &b[i], h, tau))
+                    .collect();
+                a = Self::accelerations(world, &x, &v);
+
+                for i in 0..len {
+                    let mut value = (a[i] - a0[i]) / tau;
+                    for m in 1..n {
+                        value = (value - g[i][m - 1]) / (tau - SPACINGS[m]);
+                    }
+                    let change = value - g[i][n - 1];
+                    g[i][n - 1] = value;
+                    for (b, newton) in b[i].iter_mut().zip(&self.newton[n - 1])
+                    {
+                        *b += newton * change;
+                    }
+
+                    if n == 7 {
+                        changes[i] = change;
+                    }
+                }
+            }
+
+            let max = |v: &[f64]| v.iter().fold(0_f64, |m, c| m.max(c.abs()));
+            let error = max(&changes) / max(&a);
+            if !error.is_normal()
+                || error < 1e-16
+                || (iteration > 1 && error >= last_error)
+            {
+                break;
+            }
+            last_error = error;
+        }
+
+        b
+    }
+
+    /// Largest ratio of `values` to the acceleration over all bodies, both
+    /// given as flattened vectors.
+    fn relative_error(values: &[f64], a: &[f64]) -> f64 {
+        let norm =
+            |c: &[f64]| (c[0].powi(2) + c[1].powi(2) + c[2].powi(2)).sqrt();
+        values
+            .chunks(3)
+            .zip(a.chunks(3))
+            .filter_map(|(values, a)| {
+                let a = norm(a);
+                (a > 0.).then_some(norm(values) / a)
+            })
+            .fold(0., f64::max)
+    }
+}
+
+impl Integrator for Ias15 {
+    fn step(&mut self, world: &mut World, delta_t: f64) -> f64 {
+        let proposal = self.next_step.unwrap_or(INITIAL_STEP);
+        let mut h = proposal.min(delta_t.abs()).copysign(delta_t);
+
+        let state = world.state();
+        let x0 = Self::flatten(&state.pos);
+        let v0 = Self::flatten(&state.vel);
+        let a0 = Self::flatten(&world.accelerations());
+
+        loop {
+            let predicted = self.predict(x0.len(), h);
+            let b = self.iterate(world, (&x0, &v0, &a0), h, predicted);
+            let b6: Vec<f64> = b.iter().map(|b| b[6]).collect();
+            let error = Self::relative_error(&b6, &a0);
+            let ratio = if error > 0. {
+                (self.epsilon / error).powf(1. / 7.)
+            } else {
+                1. / SAFETY_FACTOR
+            };
+
+            if ratio < SAFETY_FACTOR {
+                h *= ratio;
+                continue;
+            }
+
+            let x: Vec<f64> = (0..x0.len())
+                .map(|i| Self::position(x0[i], v0[i], a0[i], &b[i], h, 1.))
+                .collect();
+            let v: Vec<f64> = (0..v0.len())
+                .map(|i| Self::velocity(v0[i], a0[i], &b[i], h, 1.))
+                .collect();
+            world.set_state(&State {
+                pos: Self::unflatten(&x),
+                vel: Self::unflatten(&v),
+            });
+
+            let next = h.abs() * ratio.min(1. / SAFETY_FACTOR);
+            self.next_step = Some(if h == delta_t && h.abs() < proposal {
+                proposal.max(next)
+            } else {
+                next
+            });
+            self.last = Some(LastStep { b, h });
+            return h;
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("ias15:{}", self.epsilon)
+    }
+
+    fn is_adaptive(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::G;
+    use crate::world::celestials::Celestial;
+    use crate::world::config;
+
+    fn energy(world: &World) -> f64 {
+        let celestials: Vec<_> = world.celestials.values().collect();
+        let mut energy = 0.;
+        for (i, a) in celestials.iter().enumerate() {
+            energy += a.mass() * (&a.vel() * &a.vel()) / 2.;
+            for b in &celestials[i + 1..] {
+                let distance = (a.pos() - &b.pos()).normalize().distance;
+                energy -= G * a.mass() * b.mass() / distance;
+            }
+        }
+        energy
+    }
+
+    /// Heliocentric semi-major axis of the Earth-Moon barycenter, which is
+    /// free of the monthly wobble of the Earth itself.
+    fn semi_major_axis(world: &World) -> f64 {
+        let bodies = world.celestials.get();
+        let (sun, earth, moon) =
+            (&bodies["Sun"], &bodies["Earth"], &bodies["Moon"]);
+        let mass = earth.mass() + moon.mass();
+        let barycenter = |f: &dyn Fn(&Celestial) -> Vec3| {
+            (f(earth) * earth.mass() + &(f(moon) * moon.mass())) / mass
+        };
+        let r = barycenter(&|c| c.pos()) - &sun.pos();
+        let v = barycenter(&|c| c.vel()) - &sun.vel();
+
+        let mu = G * (sun.mass() + mass);
+        let energy = (&v * &v) / 2. - mu / r.normalize().distance;
+        -mu / (2. * energy)
+    }
+
+    #[test]
+    fn test_century_of_solar() {
+        let mut world = World::new(config::new_solar(), Default::default());
+        let mut ias15 = Ias15::new(Ias15::DEFAULT_EPSILON);
+
+        let energy_0 = energy(&world);
+        let semi_major_axis_0 = semi_major_axis(&world);
+
+        let year = 365.25 * 86_400.;
+        for _ in 0..100 {
+            let mut remaining = year;
+            while remaining > 0. {
+                remaining -= ias15.step(&mut world, remaining);
+            }
+
+            let energy_drift = ((energy(&world) - energy_0) / energy_0).abs();
+            let axis_drift = ((semi_major_axis(&world) - semi_major_axis_0)
+                / semi_major_axis_0)
+                .abs();
+            assert!(energy_drift < 1e-12, "energy drift {energy_drift}");
+            assert!(axis_drift < 1e-7, "semi-major axis drift {axis_drift}");
+        }
+    }
+}
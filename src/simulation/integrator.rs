@@ -0,0 +1,374 @@
+pub use super::encke::Encke;
+pub use super::ias15::Ias15;
+pub use super::patched_conics::PatchedConics;
+use crate::utils::Vec3;
+use crate::world::{State, World};
+
+pub trait Integrator: Send {
+    /// Advances the world by at most `delta_t` and returns the step that was
+    /// actually taken. Fixed step schemes always take the full `delta_t`. A
+    /// negative `delta_t` runs time backwards.
+    fn step(&mut self, world: &mut World, delta_t: f64) -> f64;
+
+    /// The name [`from_name`] turns back into this integrator.
+    fn name(&self) -> String;
+
+    /// Adaptive schemes pick their own step and are handed all the time that
+    /// is due instead of a fixed `delta_t`, which may be infinite.
+    fn is_adaptive(&self) -> bool {
+        false
+    }
+}
+
+/// First step of adaptive schemes, before there is an error estimate to size
+/// it from.
+pub(super) const INITIAL_STEP: f64 = 1.;
+
+/// Parses `euler`, `verlet`, `yoshida4`, `dopri5[:tolerance]`,
+/// `ias15[:epsilon]`, `encke` or `patched-conics`.
+pub fn from_name(name: &str) -> Option<Box<dyn Integrator>> {
+    let (name, tolerance) = match name.split_once(':') {
+        Some((name, tolerance)) => (name, Some(tolerance.parse().ok()?)),
+        None => (name, None),
+    };
+
+    match (name, tolerance) {
+        ("euler", None) => Some(Box::new(SemiImplicitEuler)),
+        ("verlet", None) => Some(Box::new(VelocityVerlet)),
+        ("yoshida4", None) => Some(Box::new(Yoshida4)),
+        ("dopri5", tolerance) => Some(Box::new(DormandPrince::new(
+            tolerance.unwrap_or(DormandPrince::DEFAULT_TOLERANCE),
+        ))),
+        ("ias15", epsilon) => Some(Box::new(Ias15::new(
+            epsilon.unwrap_or(Ias15::DEFAULT_EPSILON),
+        ))),
+        ("encke", None) => Some(Box::new(Encke::default())),
+        ("patched-conics", None) => Some(Box::new(PatchedConics::default())),
+        _ => None,
+    }
+}
+
+/// First order, kicks with the accelerations at the start of the step and
+/// then drifts with the updated velocities.
+pub struct SemiImplicitEuler;
+
+impl Integrator for SemiImplicitEuler {
+    fn step(&mut self, world: &mut World, delta_t: f64) -> f64 {
+        world.kick(delta_t);
+        world.drift(delta_t);
+        delta_t
+    }
+
+    fn name(&self) -> String {
+        "euler".to_string()
+    }
+}
+
+/// Second order symplectic kick-drift-kick scheme.
+pub struct VelocityVerlet;
+
+impl Integrator for VelocityVerlet {
+    fn step(&mut self, world: &mut World, delta_t: f64) -> f64 {
+        world.kick(delta_t / 2.);
+        world.drift(delta_t);
+        world.kick(delta_t / 2.);
+        delta_t
+    }
+
+    fn name(&self) -> String {
+        "verlet".to_string()
+    }
+}
+
+/// Fourth order symplectic scheme, three leapfrog stages with Yoshida's
+/// triple jump weights.
+pub struct Yoshida4;
+
+impl Yoshida4 {
+    const W1: f64 = 1. / (2. - 1.259_921_049_894_873_2);
+    const W0: f64 = -1.259_921_049_894_873_2 * Self::W1;
+}
+
+impl Integrator for Yoshida4 {
+    fn step(&mut self, world: &mut World, delta_t: f64) -> f64 {
+        let c1 = Self::W1 / 2.;
+        let c2 = (Self::W0 + Self::W1) / 2.;
+
+        world.drift(c1 * delta_t);
+        world.kick(Self::W1 * delta_t);
+        world.drift(c2 * delta_t);
+        world.kick(Self::W0 * delta_t);
+        world.drift(c2 * delta_t);
+        world.kick(Self::W1 * delta_t);
+        world.drift(c1 * delta_t);
+        delta_t
+    }
+
+    fn name(&self) -> String {
+        "yoshida4".to_string()
+    }
+}
+
+/// Adaptive Dormand-Prince 5(4) Runge-Kutta scheme. The step size is chosen
+/// so that the estimated local error stays below `tolerance` relative to the
+/// magnitude of each state component.
+pub struct DormandPrince {
+    tolerance: f64,
+    /// Size of the next step, whichever way time runs.
+    next_step: Option<f64>,
+}
+
+impl DormandPrince {
+    pub const DEFAULT_TOLERANCE: f64 = 1e-12;
+    /// Smallest step in s. Error control gives up there and takes the step
+    /// whatever its error, so that a NaN state cannot stall the run.
+    const MIN_STEP: f64 = 1e-9;
+
+    const A: [&'static [f64]; 6] = [
+        &[1. / 5.],
+        &[3. / 40., 9. / 40.],
+        &[44. / 45., -56. / 15., 32. / 9.],
+        &[
+            19372. / 6561.,
+            -25360. / 2187.,
+            64448. / 6561.,
+            -212. / 729.,
+        ],
+        &[
+            9017. / 3168.,
+            -355. / 33.,
+            46732. / 5247.,
+            49. / 176.,
+            -5103. / 18656.,
+        ],
+        &[
+            35. / 384.,
+            0.,
+            500. / 1113.,
+            125. / 192.,
+            -2187. / 6784.,
+            11. / 84.,
+        ],
+    ];
+    const E: [f64; 7] = [
+        71. / 57600.,
+        0.,
+        -71. / 16695.,
+        71. / 1920.,
+        -17253. / 339200.,
+        22. / 525.,
+        -1. / 40.,
+    ];
+
+    pub fn new(tolerance: f64) -> Self {
+        Self {
+            tolerance,
+            next_step: None,
+        }
+    }
+
+    fn derivative(world: &mut World, state: &State) -> State {
+        world.set_state(state);
+        State {
+            pos: state.vel.clone(),
+            vel: world.accelerations(),
+        }
+    }
+
+    fn combine(y: &State, h: f64, weights: &[f64], k: &[State]) -> State {
+        let mut res = y.clone();
+        for (weight, k) in weights.iter().zip(k) {
+            if *weight == 0. {
+                continue;
+            }
+            for (pos, dpos) in res.pos.iter_mut().zip(&k.pos) {
+                *pos += dpos * (h * weight);
+            }
+            for (vel, dvel) in res.vel.iter_mut().zip(&k.vel) {
+                *vel += dvel * (h * weight);
+            }
+        }
+        res
+    }
+
+    /// Returns the fifth order solution and its scaled error norm.
+    fn try_step(&self, world: &mut World, y: &State, h: f64) -> (State, f64) {
+        let mut k = vec![Self::derivative(world, y)];
+        for weights in Self::A {
+            let stage = Self::combine(y, h, weights, &k);
+            k.push(Self::derivative(world, &stage));
+        }
+        let y_new = Self::combine(y, h, Self::A[5], &k);
+        let zero = State {
+            pos: vec![Vec3::default(); y.pos.len()],
+            vel: vec![Vec3::default(); y.vel.len()],
+        };
+        let error = Self::combine(&zero, h, &Self::E, &k);
+
+        let mut sum = 0.;
+        let mut count = 0;
+        let states = y
+            .pos
+            .iter()
+            .zip(&y_new.pos)
+            .zip(&error.pos)
+            .chain(y.vel.iter().zip(&y_new.vel).zip(&error.vel));
+        for ((old, new), error) in states {
+            for (old, new, error) in [
+                (old.x, new.x, error.x),
+                (old.y, new.y, error.y),
+                (old.z, new.z, error.z),
+            ] {
+                let scale = self.tolerance * (1. + old.abs().max(new.abs()));
+                sum += (error / scale).powi(2);
+                count += 1;
+            }
+        }
+        let norm = (sum / count.max(1) as f64).sqrt();
+
+        (y_new, norm)
+    }
+}
+
+impl Integrator for DormandPrince {
+    fn step(&mut self, world: &mut World, delta_t: f64) -> f64 {
+        let proposal = self.next_step.unwrap_or(INITIAL_STEP);
+        let mut h = proposal.min(delta_t.abs()).copysign(delta_t);
+        let y = world.state();
+
+        loop {
+            let (y_new, error) = self.try_step(world, &y, h);
+            // A NaN error, as from a state that has overflowed, rejects
+            // the step as firmly as possible.
+            let factor = if error.is_nan() {
+                0.2
+            } else {
+                (0.9 * error.powf(-0.2)).clamp(0.2, 5.)
+            };
+
+            if error <= 1. || h.abs() <= Self::MIN_STEP {
+                world.set_state(&y_new);
+                let next = (h.abs() * factor).max(Self::MIN_STEP);
+                self.next_step = Some(if h == delta_t && h.abs() < proposal {
+                    proposal.max(next)
+                } else {
+                    next
+                });
+                return h;
+            }
+            h = (h * factor).abs().max(Self::MIN_STEP).copysign(h);
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("dopri5:{}", self.tolerance)
+    }
+
+    fn is_adaptive(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::G;
+    use crate::world::celestials::{Celestial, Celestials};
+
+    fn circular_orbit() -> World {
+        let star_mass = 2_f64 * 10_f64.powi(30);
+        let radius = 1.5_f64 * 10_f64.powi(11);
+        let mut celestials = Celestials::new();
+        celestials.add(Celestial::new(
+            "Star".to_string(),
+            star_mass,
+            Vec3::default(),
+            Vec3::default(),
+            1.,
+        ));
+        celestials.add(Celestial::new(
+            "Planet".to_string(),
+            1.,
+            Vec3 {
+                x: radius,
+                y: 0.,
+                z: 0.,
+            },
+            Vec3 {
+                x: 0.,
+                y: (G * star_mass / radius).sqrt(),
+                z: 0.,
+            },
+            1.,
+        ));
+        World::new(celestials, Default::default())
+    }
+
+    fn max_radius_error(integrator: &mut dyn Integrator) -> f64 {
+        let mut world = circular_orbit();
+        let radius = world.get_bodies()["Planet"].pos().normalize().distance;
+        let delta_t = 86_400.;
+
+        let mut max_error: f64 = 0.;
+        for _ in 0..365 {
+            let mut remaining = delta_t;
+            while remaining > 0. {
+                remaining -= integrator.step(&mut world, remaining);
+            }
+            let r = world.get_bodies()["Planet"].pos().normalize().distance;
+            max_error = max_error.max((r - radius).abs() / radius);
+        }
+        max_error
+    }
+
+    #[test]
+    fn test_order_of_accuracy() {
+        let euler = max_radius_error(&mut SemiImplicitEuler);
+        let verlet = max_radius_error(&mut VelocityVerlet);
+        let yoshida = max_radius_error(&mut Yoshida4);
+        let dopri = max_radius_error(&mut DormandPrince::new(1e-12));
+
+        assert!(euler < 2e-2);
+        assert!(verlet < 5e-4);
+        assert!(yoshida < 5e-7);
+        assert!(dopri < 1e-9);
+    }
+
+    #[test]
+    fn test_gives_up_on_a_nan_error() {
+        let mut world = circular_orbit();
+        let planet = world.celestials.get_mut("Planet").unwrap();
+        let nan = Vec3 {
+            x: f64::NAN,
+            y: 0.,
+            z: 0.,
+        };
+        planet.set_state(planet.pos(), nan);
+        let mut dopri = DormandPrince::new(1e-12);
+        assert_eq!(dopri.step(&mut world, 1.), DormandPrince::MIN_STEP);
+    }
+
+    #[test]
+    fn test_time_reversal() {
+        let integrators: [(Box<dyn Integrator>, f64); 4] = [
+            (Box::new(VelocityVerlet), 1e-4),
+            (Box::new(Yoshida4), 1e-4),
+            (Box::new(DormandPrince::new(1e-12)), 1.),
+            (Box::new(Ias15::new(Ias15::DEFAULT_EPSILON)), 1.),
+        ];
+        for (mut integrator, tolerance) in integrators {
+            let mut world = circular_orbit();
+            let start = world.get_bodies()["Planet"].pos();
+            for delta_t in [86_400_f64, -86_400.] {
+                for _ in 0..100 {
+                    let mut remaining = delta_t;
+                    while remaining != 0. {
+                        remaining -= integrator.step(&mut world, remaining);
+                    }
+                }
+            }
+            let end = world.get_bodies()["Planet"].pos();
+            assert!(end.equal_to(&start, tolerance), "{:?} {:?}", start, end);
+        }
+    }
+}
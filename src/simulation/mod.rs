@@ -1,4 +1,12 @@
 #[allow(clippy::module_inception)]
 mod simulation;
+mod encke;
+mod history;
+mod ias15;
+pub mod integrator;
+mod patched_conics;
+mod rails;
+pub mod snapshot;
 
-pub use simulation::Simulation;
+pub use integrator::Integrator;
+pub use simulation::{Simulation, HISTORY_EVERY, HISTORY_LENGTH};
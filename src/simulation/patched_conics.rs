@@ -0,0 +1,275 @@
+use super::integrator::{Integrator, Yoshida4};
+use crate::utils::G;
+use crate::world::kepler;
+use crate::world::soi::Sphere;
+use crate::world::World;
+use std::collections::HashMap;
+use std::f64::consts::PI;
+
+/// Quick look in the style of patched conics. Celestials are integrated as
+/// usual, but every spaceship follows a Kepler conic around the celestial
+/// whose Laplace sphere of influence it is in, however long the step, and
+/// changes to another celestial when it crosses into that one's sphere.
+/// The switches are recorded on [`World::soi_switches`]. Spaceships feel
+/// nothing but their reference celestial and drift in a straight line when
+/// there is none. Steps are as long as the celestials allow.
+#[derive(Default)]
+pub struct PatchedConics {
+    /// Reference celestial of each spaceship.
+    references: HashMap<String, String>,
+}
+
+impl PatchedConics {
+    /// Steps per orbit of the fastest celestial around its parent.
+    const STEPS_PER_ORBIT: f64 = 100.;
+    /// Longest step in s, which bounds how late a spaceship is noticed to
+    /// have crossed into another sphere of influence.
+    const MAX_STEP: f64 = 86_400.;
+
+    /// Longest step that still integrates the celestials accurately.
+    fn max_step(world: &World, spheres: &HashMap<String, Sphere>) -> f64 {
+        let mut max_step = Self::MAX_STEP;
+        for celestial in world.celestials.values() {
+            let Some(parent) = &spheres[&celestial.name()].parent else {
+                continue;
+            };
+            let parent = world.celestials.get_ref(parent).unwrap();
+            let distance =
+                (celestial.pos() - &parent.pos()).normalize().distance;
+            let mu = G * (parent.mass() + celestial.mass());
+            let period = 2. * PI * (distance.powi(3) / mu).sqrt();
+            max_step = max_step.min(period / Self::STEPS_PER_ORBIT);
+        }
+        max_step
+    }
+}
+
+impl Integrator for PatchedConics {
+    fn step(&mut self, world: &mut World, delta_t: f64) -> f64 {
+        let spheres = world.celestials.spheres_of_influence();
+        let delta_t = delta_t
+            .abs()
+            .min(Self::max_step(world, &spheres))
+            .copysign(delta_t);
+        let spaceships = std::mem::take(&mut world.spaceships);
+
+        let mut conics = Vec::new();
+        for (name, spaceship) in spaceships {
+            let reference = self
+                .references
+                .get(&name)
+                .and_then(|r| world.celestials.get_ref(r))
+                .or_else(|| {
+                    let dominant =
+                        world.celestials.dominant(&spheres, &spaceship.pos());
+                    world.celestials.get_ref(&dominant?)
+                })
+                .map(|celestial| {
+                    self.references.insert(name.clone(), celestial.name());
+                    (
+                        celestial.name(),
+                        spaceship.pos() - &celestial.pos(),
+                        spaceship.vel() - &celestial.vel(),
+                        G * celestial.mass(),
+                    )
+                });
+            conics.push((spaceship, reference));
+        }
+
+        Yoshida4.step(world, delta_t);
+
+        let spheres = world.celestials.spheres_of_influence();
+        for (mut spaceship, reference) in conics {
+            let name = spaceship.name();
+            if let Some((reference, pos, vel, mu)) = reference {
+                let celestial = world.celestials.get_ref(&reference).unwrap();
+                let (pos, vel) = kepler::propagate(mu, &pos, &vel, delta_t);
+                spaceship
+                    .set_state(pos + &celestial.pos(), vel + &celestial.vel());
+
+                let dominant =
+                    world.celestials.dominant(&spheres, &spaceship.pos());
+                if let Some(dominant) = dominant.filter(|d| *d != reference) {
+                    world.record_soi_switch(
+                        world.time + delta_t,
+                        &name,
+                        &reference,
+                        &dominant,
+                    );
+                    self.references.insert(name.clone(), dominant);
+                }
+            } else {
+                let vel = spaceship.vel();
+                spaceship
+                    .set_state(spaceship.pos() + &(vel.clone() * delta_t), vel);
+            }
+            world.spaceships.insert(name, spaceship);
+        }
+        delta_t
+    }
+
+    fn name(&self) -> String {
+        "patched-conics".to_string()
+    }
+
+    fn is_adaptive(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::Vec3;
+    use crate::world::celestials::{Celestial, Celestials};
+    use crate::world::config;
+    use crate::world::spaceship::Spaceship;
+    use std::collections::BTreeMap;
+
+    /// Sun, Earth and Moon as point masses and one spaceship at `offset`
+    /// from the Earth, moving with `vel` relative to it.
+    fn world(offset: Vec3, vel: Vec3) -> World {
+        let mut celestials = Celestials::new();
+        for c in [config::sun(), config::earth(), config::moon()] {
+            celestials.add(Celestial::new(
+                c.name(),
+                c.mass(),
+                c.pos(),
+                c.vel(),
+                c.rad(),
+            ));
+        }
+        let earth = config::earth();
+        let spaceship = Spaceship::new(
+            "Probe".to_string(),
+            1000.,
+            offset + &earth.pos(),
+            vel + &earth.vel(),
+        );
+        let mut spaceships = BTreeMap::new();
+        spaceships.insert(spaceship.name(), spaceship);
+        World::new(celestials, spaceships)
+    }
+
+    fn run(
+        world: &mut World,
+        integrator: &mut dyn Integrator,
+        duration: f64,
+        steps: usize,
+    ) {
+        let delta_t = duration / steps as f64;
+        for _ in 0..steps {
+            let mut left = delta_t;
+            while left > 0. {
+                let taken = integrator.step(world, left);
+                world.time += taken;
+                left -= taken;
+            }
+        }
+    }
+
+    #[test]
+    fn test_drifts_without_celestials() {
+        let vel = Vec3 {
+            x: 1e3,
+            y: 0.,
+            z: 0.,
+        };
+        let spaceship = Spaceship::new(
+            "Probe".to_string(),
+            1000.,
+            Vec3::default(),
+            vel.clone(),
+        );
+        let mut spaceships = BTreeMap::new();
+        spaceships.insert(spaceship.name(), spaceship);
+        let mut world = World::new(Celestials::new(), spaceships);
+
+        let taken = PatchedConics::default().step(&mut world, 1e3);
+        assert_eq!(taken, 1e3);
+        assert!(world.spaceships["Probe"].pos().equal_to(&(vel * 1e3), 0.));
+    }
+
+    #[test]
+    fn test_steps_a_fraction_of_the_moons_orbit() {
+        let offset = Vec3 {
+            x: 7e6,
+            y: 0.,
+            z: 0.,
+        };
+        let vel = Vec3 {
+            x: 0.,
+            y: 7546.,
+            z: 0.,
+        };
+        let mut world = world(offset, vel);
+        let taken = PatchedConics::default().step(&mut world, 86_400.);
+        // The Moon goes round the Earth in about 27.3 days.
+        assert!(taken > 2e4 && taken < 3e4, "{taken}");
+    }
+
+    #[test]
+    fn test_matches_n_body_in_low_orbit() {
+        let offset = Vec3 {
+            x: 7e6,
+            y: 0.,
+            z: 0.,
+        };
+        let vel = Vec3 {
+            x: 0.,
+            y: 7546.,
+            z: 0.,
+        };
+        let period = 2.
+            * std::f64::consts::PI
+            * (7e6_f64.powi(3) / (G * config::earth().mass())).sqrt();
+
+        let mut n_body = world(offset.clone(), vel.clone());
+        run(&mut n_body, &mut Yoshida4, period, 6000);
+        let mut conics = world(offset, vel);
+        run(&mut conics, &mut PatchedConics::default(), period, 10);
+
+        // Only the tides of the Sun and the Moon tell them apart.
+        let error = (conics.spaceships["Probe"].pos()
+            - &n_body.spaceships["Probe"].pos())
+            .normalize()
+            .distance;
+        assert!(error < 1e3, "{error}");
+        assert!(conics.soi_switches.is_empty());
+    }
+
+    #[test]
+    fn test_reports_escape_from_earth() {
+        let offset = Vec3 {
+            x: 0.,
+            y: -7e6,
+            z: 0.,
+        };
+        let vel = Vec3 {
+            x: 0.,
+            y: -12_000.,
+            z: 0.,
+        };
+        let mut world = world(offset, vel);
+        run(
+            &mut world,
+            &mut PatchedConics::default(),
+            10. * 86_400.,
+            240,
+        );
+
+        let switches: Vec<_> = world
+            .soi_switches
+            .iter()
+            .map(|s| (s.from.as_str(), s.to.as_str()))
+            .collect();
+        assert_eq!(switches, [("Earth", "Sun")]);
+
+        let spheres = world.celestials.spheres_of_influence();
+        let earth = world.celestials.get_ref("Earth").unwrap();
+        let distance = (world.spaceships["Probe"].pos() - &earth.pos())
+            .normalize()
+            .distance;
+        assert!(distance > spheres["Earth"].radius);
+    }
+}
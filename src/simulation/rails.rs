@@ -0,0 +1,210 @@
+use crate::utils::{Vec3, G};
+use crate::world::celestials::Celestial;
+use crate::world::kepler;
+use crate::world::World;
+use std::collections::HashMap;
+
+/// Moves every body and particle along its Kepler conic through `delta_t`
+/// in one go, however long, and returns whether it could. The heaviest
+/// celestial drifts in a straight line, the other celestials orbit the
+/// parent of their sphere of influence and spaceships and particles the
+/// celestial whose sphere they are in, so perturbations, drag and radiation
+/// pressure are left out. The world is left as it was when a periapsis lies
+/// below the surface of the parent or a body ends up in another sphere of
+/// influence, so that close encounters can be integrated numerically
+/// instead.
+pub fn step(world: &mut World, delta_t: f64) -> bool {
+    let start = world.celestials.clone();
+    let spheres = start.spheres_of_influence();
+
+    // Parents are heavier, so they have moved by the time their moons do.
+    let mut celestials: Vec<&Celestial> = start.values().collect();
+    celestials.sort_by(|a, b| b.mass().total_cmp(&a.mass()));
+    let mut moved: HashMap<String, (Vec3, Vec3)> = HashMap::new();
+    for celestial in celestials {
+        let state = match &spheres[&celestial.name()].parent {
+            None => (
+                celestial.pos() + &(celestial.vel() * delta_t),
+                celestial.vel(),
+            ),
+            Some(parent) => {
+                let parent = start.get_ref(parent).unwrap();
+                let Some(state) = conic(
+                    parent,
+                    &moved[&parent.name()],
+                    celestial.pos(),
+                    celestial.vel(),
+                    celestial.mass(),
+                    celestial.rad(),
+                    delta_t,
+                ) else {
+                    return false;
+                };
+                state
+            }
+        };
+        moved.insert(celestial.name(), state);
+    }
+
+    let mut spaceships = Vec::new();
+    for spaceship in world.spaceships.values() {
+        let Some(parent) = start.dominant(&spheres, &spaceship.pos()) else {
+            return false;
+        };
+        let Some(state) = conic(
+            start.get_ref(&parent).unwrap(),
+            &moved[&parent],
+            spaceship.pos(),
+            spaceship.vel(),
+            spaceship.mass(),
+            0.,
+            delta_t,
+        ) else {
+            return false;
+        };
+        spaceships.push((spaceship.name(), parent, state));
+    }
+
+    for celestial in world.celestials.values_mut() {
+        let (pos, vel) = moved.remove(&celestial.name()).unwrap();
+        celestial.set_state(pos, vel);
+    }
+    let new_spheres = world.celestials.spheres_of_influence();
+    let stayed = spheres.iter().all(|(name, sphere)| {
+        new_spheres.get(name).map(|s| &s.parent) == Some(&sphere.parent)
+    }) && spaceships.iter().all(|(_, parent, (pos, _))| {
+        world.celestials.dominant(&new_spheres, pos).as_ref() == Some(parent)
+    });
+    if !stayed {
+        world.celestials = start;
+        return false;
+    }
+
+    for (name, _, (pos, vel)) in spaceships {
+        world.spaceships.get_mut(&name).unwrap().set_state(pos, vel);
+    }
+
+    // Particles are never refused and follow their conics wherever they
+    // lead, even through the surface.
+    for i in 0..world.particles.len() {
+        let pos = world.particles.pos(i);
+        let Some(parent) = start.dominant(&spheres, &pos) else {
+            continue;
+        };
+        let before = start.get_ref(&parent).unwrap();
+        let after = world.celestials.get_ref(&parent).unwrap();
+        let (pos, vel) = kepler::propagate(
+            G * before.mass(),
+            &(pos - &before.pos()),
+            &(world.particles.vel(i) - &before.vel()),
+            delta_t,
+        );
+        world
+            .particles
+            .set_state(i, pos + &after.pos(), vel + &after.vel());
+    }
+    true
+}
+
+/// State of a body of `mass` and radius `rad` at `pos` with `vel` after
+/// `delta_t` on its conic around `parent`, which has meanwhile moved to
+/// `moved`. None if the conic passes below the parent's surface.
+fn conic(
+    parent: &Celestial,
+    moved: &(Vec3, Vec3),
+    pos: Vec3,
+    vel: Vec3,
+    mass: f64,
+    rad: f64,
+    delta_t: f64,
+) -> Option<(Vec3, Vec3)> {
+    let mu = G * (parent.mass() + mass);
+    let pos = pos - &parent.pos();
+    let vel = vel - &parent.vel();
+    if kepler::periapsis(mu, &pos, &vel) < parent.rad() + rad {
+        return None;
+    }
+    let (pos, vel) = kepler::propagate(mu, &pos, &vel, delta_t);
+    Some((pos + &moved.0, vel + &moved.1))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulation::integrator::{Integrator, Yoshida4};
+    use crate::world::config;
+    use crate::world::spaceship::Spaceship;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_low_orbit_survives_a_month() {
+        let mut world = World::new(config::new_solar(), BTreeMap::new());
+        let earth = config::earth();
+        let radius = earth.rad() + 400_000.;
+        let speed = (G * earth.mass() / radius).sqrt();
+        let spaceship = Spaceship::new(
+            "Probe".to_string(),
+            1000.,
+            earth.pos()
+                + &Vec3 {
+                    x: radius,
+                    y: 0.,
+                    z: 0.,
+                },
+            earth.vel()
+                + &Vec3 {
+                    x: 0.,
+                    y: speed,
+                    z: 0.,
+                },
+        );
+        world.spaceships.insert(spaceship.name(), spaceship);
+
+        // A day per step would fling the probe away with any integrator.
+        for _ in 0..30 {
+            assert!(step(&mut world, 86_400.));
+        }
+        let earth = world.celestials.get_ref("Earth").unwrap();
+        let altitude = (world.spaceships["Probe"].pos() - &earth.pos())
+            .normalize()
+            .distance
+            - earth.rad();
+        assert!((altitude - 400_000.).abs() < 1., "{altitude}");
+
+        let mut numerical = world.clone();
+        Yoshida4.step(&mut numerical, 86_400.);
+        let earth = numerical.celestials.get_ref("Earth").unwrap();
+        let distance = (numerical.spaceships["Probe"].pos() - &earth.pos())
+            .normalize()
+            .distance;
+        assert!(distance > 1e7, "{distance}");
+    }
+
+    #[test]
+    fn test_refuses_close_encounters() {
+        let mut world = World::new(config::new_solar(), BTreeMap::new());
+        let earth = config::earth();
+        // Falling straight down from 1000 km.
+        let spaceship = Spaceship::new(
+            "Probe".to_string(),
+            1000.,
+            earth.pos()
+                + &Vec3 {
+                    x: earth.rad() + 1e6,
+                    y: 0.,
+                    z: 0.,
+                },
+            earth.vel(),
+        );
+        world.spaceships.insert(spaceship.name(), spaceship);
+        let state = world.state();
+
+        assert!(!step(&mut world, 60.));
+        assert!(world
+            .state()
+            .pos
+            .iter()
+            .zip(&state.pos)
+            .all(|(a, b)| a.equal_to(b, 0.)));
+    }
+}
@@ -1,54 +1,142 @@
+use super::history::History;
+use super::snapshot::Snapshot;
+use super::{rails, Integrator};
 use crate::gui::ControlMessage;
 use crate::World;
 use std::time::Duration;
 use tokio::sync::mpsc::error::TryRecvError;
 use tokio::sync::{mpsc, watch};
-use tokio::time::{interval, Instant};
+use tokio::time::{interval, Instant, MissedTickBehavior};
+
+/// Wall-clock ticks per second. Every tick handles control messages, runs
+/// as many physics steps as the time speed asks for and publishes the world.
+const TICK_RATE: f64 = 100.;
+
+/// Simulated seconds per second from which bodies move on rails, when
+/// that is allowed.
+const RAILS_TIME_SPEED: f64 = 10_000.;
+
+/// States kept in the history by default.
+pub const HISTORY_LENGTH: usize = 1000;
+/// Simulated seconds between states of the history by default.
+pub const HISTORY_EVERY: f64 = 60.;
 
 pub struct Simulation {
     world: World,
     world_publisher: watch::Sender<World>,
     control: mpsc::Receiver<ControlMessage>,
+    integrator: Box<dyn Integrator>,
     time_speed: f64,
-    simulation_fps: u32,
+    max_speed: bool,
     delta_t: f64,
+    /// Simulated time that is due but not stepped yet.
+    pending: f64,
+    /// Set when a collision outcome asked to stop.
+    stopped: bool,
+    /// Physics steps taken since the start.
+    steps: u64,
+    /// Set when a spaceship changed its velocity during the current tick.
+    maneuvered: bool,
+    /// Whether bodies may move on rails at high time speed, leaving out
+    /// everything but the pull of a single celestial.
+    allow_rails: bool,
+    /// Recent states of the world to jump back to.
+    history: History,
+    /// Where snapshots are saved to and loaded from.
+    snapshot_path: String,
 }
 
 impl Simulation {
     pub fn new(
-        world: World,
-        simulation_fps: u32,
+        mut world: World,
+        delta_t: f64,
         time_speed: f64,
+        integrator: Box<dyn Integrator>,
         control: mpsc::Receiver<ControlMessage>,
     ) -> (Self, watch::Receiver<World>) {
+        if world.barycentric {
+            world.center_on_barycenter();
+            world.barycentric = false;
+        }
+        world.update_diagnostics();
         let (world_publisher, world_watch) = watch::channel(world.clone());
         (
             Self {
                 world,
                 world_publisher,
                 control,
+                integrator,
                 time_speed,
-                simulation_fps,
-                delta_t: time_speed / simulation_fps as f64,
+                max_speed: false,
+                delta_t,
+                pending: 0.,
+                stopped: false,
+                steps: 0,
+                maneuvered: false,
+                allow_rails: false,
+                history: History::new(HISTORY_LENGTH, HISTORY_EVERY),
+                snapshot_path: "snapshot.json".to_string(),
             },
             world_watch,
         )
     }
 
+    pub fn set_snapshot_path(&mut self, path: String) {
+        self.snapshot_path = path;
+    }
+
+    pub fn set_rails(&mut self, allow: bool) {
+        self.allow_rails = allow;
+    }
+
+    /// Keeps `length` states of the world, `every` simulated seconds apart.
+    pub fn set_history(&mut self, length: usize, every: f64) {
+        self.history = History::new(length, every);
+    }
+
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            world: self.world.clone(),
+            integrator: self.integrator.name(),
+            delta_t: self.delta_t,
+        }
+    }
+
+    /// Continues from `snapshot` with its integrator and step. The history
+    /// belongs to another run and is dropped.
+    pub fn restore(&mut self, snapshot: Snapshot) -> Result<(), String> {
+        self.integrator = snapshot.integrator()?;
+        self.world = snapshot.world;
+        self.delta_t = snapshot.delta_t;
+        self.pending = 0.;
+        self.history.clear();
+        Ok(())
+    }
+
     pub async fn spin(&mut self) -> Result<(), String> {
-        let mut interval =
-            interval(Duration::from_secs_f64(1. / self.simulation_fps as f64));
+        let period = Duration::from_secs_f64(1. / TICK_RATE);
+        let mut interval = interval(period);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
 
+        let mut last_tick = Instant::now();
         let mut start = Instant::now();
-        let mut fps_counter = 0;
+        let mut start_time = self.world.time;
+        let mut step_counter = 0;
 
         loop {
             interval.tick().await;
-            fps_counter += 1;
+            let now = Instant::now();
+            let elapsed = now - last_tick;
+            last_tick = now;
+
             if start.elapsed().as_secs() >= 1 {
-                self.world.true_sim_fps = fps_counter;
+                let wall = start.elapsed().as_secs_f64();
+                self.world.true_sim_fps = step_counter;
+                self.world.true_time_speed =
+                    (self.world.time - start_time) / wall;
                 start = Instant::now();
-                fps_counter = 0;
+                start_time = self.world.time;
+                step_counter = 0;
             }
 
             loop {
@@ -56,32 +144,254 @@ impl Simulation {
                     Ok(ControlMessage::Shutdown)
                     | Err(TryRecvError::Disconnected) => return Ok(()),
                     Ok(ControlMessage::Speedup) => {
-                        self.world
-                            .spaceships
-                            .get_mut("ISS2")
-                            .unwrap()
-                            .speedup();
+                        if let Some(spaceship) =
+                            self.world.spaceships.get_mut("ISS2")
+                        {
+                            spaceship.speedup();
+                            self.maneuvered = true;
+                        }
+                    }
+                    Ok(ControlMessage::Torque(torque)) => {
+                        if let Some(spaceship) =
+                            self.world.spaceships.get_mut("ISS2")
+                        {
+                            spaceship.set_torque(torque);
+                        }
                     }
                     Ok(ControlMessage::SetTimeSpeed(speed)) => {
                         self.time_speed = speed;
-                        self.delta_t = self.time_speed / self.simulation_fps as f64;
-                    },
+                        self.max_speed = false;
+                        self.pending = 0.;
+                    }
+                    Ok(ControlMessage::MaxSpeed) => {
+                        self.max_speed = true;
+                    }
+                    Ok(ControlMessage::Reverse) => {
+                        self.time_speed = -self.time_speed;
+                        self.pending = 0.;
+                    }
+                    Ok(ControlMessage::Save) => {
+                        let path = &self.snapshot_path;
+                        if let Err(e) = self.snapshot().save(path) {
+                            eprintln!("{}", e);
+                        }
+                    }
+                    Ok(ControlMessage::Load) => {
+                        let snapshot = Snapshot::load(&self.snapshot_path);
+                        if let Err(e) = snapshot.and_then(|s| self.restore(s)) {
+                            eprintln!("{}", e);
+                        }
+                    }
+                    Ok(ControlMessage::Rewind) => {
+                        let time = self.world.time;
+                        if let Some(world) = self.history.before(time) {
+                            self.jump(world.clone());
+                        }
+                    }
+                    Ok(ControlMessage::Jump(time)) => {
+                        if let Some(world) = self.history.nearest(time) {
+                            self.jump(world.clone());
+                        }
+                    }
                     _ => break,
                 }
             }
 
-            for spaceship in self.world.spaceships.values_mut() {
-                let a = self
-                    .world
-                    .celestials
-                    .get_global_acceleration(spaceship.pos());
-                spaceship.apply_gravity(a, self.delta_t);
+            // Maximum speed runs whichever way time runs, unless paused.
+            let span = if self.max_speed && self.time_speed != 0. {
+                f64::INFINITY.copysign(self.time_speed)
+            } else {
+                elapsed.as_secs_f64() * self.time_speed
+            };
+            self.world.on_rails = self.rails(span);
+            let steps = if self.world.on_rails {
+                1
+            } else {
+                self.advance(span, Some(now + period))
+            };
+            step_counter += steps;
+            self.maneuvered = false;
+            // A paused world must not cut off the states after it.
+            if steps > 0 {
+                self.history.record(&self.world);
+            }
+            self.world.timeline = self.history.span();
+            if self.stopped {
+                self.time_speed = 0.;
+                self.max_speed = false;
+                self.stopped = false;
             }
-            self.world.celestials.update(self.delta_t);
 
             self.world_publisher
                 .send(self.world.clone())
                 .map_err(|e| format!("World publisher died: {}", e))?;
         }
     }
+
+    /// Continues from a state of the history, paused so that the timeline
+    /// can be looked through before time runs on from there.
+    fn jump(&mut self, world: World) {
+        self.world = world;
+        self.time_speed = 0.;
+        self.max_speed = false;
+        self.pending = 0.;
+    }
+
+    /// Steps as fast as possible through `duration` simulated seconds and
+    /// hands the world to `output` at the start and then every `every`
+    /// seconds, without waiting for control messages. A collision that asks
+    /// to stop ends the run early.
+    pub fn run_until(
+        &mut self,
+        duration: f64,
+        every: f64,
+        mut output: impl FnMut(&World) -> Result<(), String>,
+    ) -> Result<(), String> {
+        let start = self.world.time;
+        output(&self.world)?;
+
+        let outputs = (duration / every).ceil() as u64;
+        for k in 1..=outputs {
+            let target = start + (k as f64 * every).min(duration);
+            self.advance(target - self.world.time - self.pending, None);
+            output(&self.world)?;
+            if self.stopped {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Steps the world through `span` simulated seconds, or until `deadline`
+    /// if that comes first, and returns the number of steps taken. Fixed step
+    /// integrators carry a remainder shorter than `delta_t` over to the next
+    /// call. Time that could not be stepped before the deadline is dropped,
+    /// so a slow machine shows up in `World::true_time_speed` instead of
+    /// piling up. A collision that asks to stop drops the rest of the span.
+    /// A negative span steps backwards in time.
+    fn advance(&mut self, span: f64, deadline: Option<Instant>) -> u32 {
+        self.pending += span;
+        let adaptive = self.integrator.is_adaptive();
+        let delta_t = self.delta_t.copysign(self.pending);
+
+        let mut steps = 0;
+        loop {
+            let limit = if adaptive { self.pending } else { delta_t };
+            // Rounding in the sum of many steps must not cost a whole step.
+            if self.pending == 0.
+                || self.pending.abs() < limit.abs() * (1. - 1e-9)
+            {
+                break;
+            }
+            if steps > 0 && deadline.is_some_and(|d| Instant::now() >= d) {
+                self.pending = 0.;
+                break;
+            }
+
+            let before = (!self.world.particles.is_empty())
+                .then(|| self.world.celestials.clone());
+            let taken = self.integrator.step(&mut self.world, limit);
+            if let Some(before) = before {
+                self.world.step_particles(&before, taken);
+            }
+            self.pending -= taken;
+            steps += 1;
+            if self.finish_step(taken) {
+                break;
+            }
+        }
+        steps
+    }
+
+    /// Moves all bodies on rails through `span` and the pending time in a
+    /// single step, see [`rails::step`]. Returns false and leaves the world
+    /// to the integrator unless rails are allowed, below `RAILS_TIME_SPEED`,
+    /// at maximum speed, in CR3BP mode, after a maneuver and ahead of close
+    /// encounters.
+    fn rails(&mut self, span: f64) -> bool {
+        if !self.allow_rails
+            || self.max_speed
+            || self.maneuvered
+            || self.time_speed.abs() < RAILS_TIME_SPEED
+            || self.world.cr3bp.is_some()
+        {
+            return false;
+        }
+        let span = span + self.pending;
+        if !rails::step(&mut self.world, span) {
+            return false;
+        }
+        self.pending = 0.;
+        self.finish_step(span);
+        true
+    }
+
+    /// Bookkeeping after a step of `taken` seconds, which also turns the
+    /// spaceships, as no integrator does. Returns whether a collision asked
+    /// to stop, in which case the pending time is dropped.
+    fn finish_step(&mut self, taken: f64) -> bool {
+        self.world.time += taken;
+        self.world.delta_t = taken;
+        self.world.rotate(taken);
+        self.steps += 1;
+        let every = self.world.diagnostics_every.max(1) as u64;
+        if self.steps.is_multiple_of(every) {
+            self.world.update_diagnostics();
+        }
+
+        self.stopped = self.world.resolve_collisions();
+        if self.stopped {
+            self.pending = 0.;
+        }
+        self.stopped
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulation::integrator::VelocityVerlet;
+    use crate::utils::Vec3;
+    use crate::world::celestials::Celestials;
+    use crate::world::collision::Outcome;
+    use crate::world::config;
+    use crate::world::spaceship::Spaceship;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_runs_on_after_a_stop() {
+        let earth = config::earth();
+        let spaceship = Spaceship::new(
+            "Probe".to_string(),
+            1000.,
+            earth.pos()
+                + &Vec3 {
+                    x: earth.rad() / 2.,
+                    y: 0.,
+                    z: 0.,
+                },
+            earth.vel(),
+        );
+        let mut celestials = Celestials::new();
+        celestials.add(earth);
+        let mut spaceships = BTreeMap::new();
+        spaceships.insert(spaceship.name(), spaceship);
+        let mut world = World::new(celestials, spaceships);
+        world.collision_policy.spaceships = Outcome::Stop;
+
+        let (_control, receiver) = mpsc::channel(1);
+        let (mut simulation, _) =
+            Simulation::new(world, 1., 1., Box::new(VelocityVerlet), receiver);
+        let mut times = Vec::new();
+        let mut output = |world: &World| -> Result<(), String> {
+            times.push(world.time);
+            Ok(())
+        };
+        simulation.run_until(10., 1., &mut output).unwrap();
+        // Resuming with the spaceship still inside runs to the end.
+        simulation.run_until(10., 1., &mut output).unwrap();
+        assert_eq!(times[..3], [0., 1., 1.]);
+        assert_eq!(times.last(), Some(&11.));
+        assert_eq!(simulation.world.collisions.len(), 1);
+    }
 }
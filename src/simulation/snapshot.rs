@@ -0,0 +1,97 @@
+use super::integrator::{self, Integrator};
+use crate::world::World;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+
+/// Everything needed to pick a simulation up exactly where it was saved,
+/// stored as JSON. Adaptive integrators keep no step size across a save, so
+/// only fixed step runs carry on bit for bit.
+#[derive(Serialize, Deserialize)]
+pub struct Snapshot {
+    pub world: World,
+    /// As [`super::integrator::from_name`] parses it.
+    pub integrator: String,
+    /// Step of fixed step integrators.
+    pub delta_t: f64,
+}
+
+impl Snapshot {
+    pub fn integrator(&self) -> Result<Box<dyn Integrator>, String> {
+        integrator::from_name(&self.integrator)
+            .ok_or(format!("Unknown integrator: {}", self.integrator))
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        let error = |e: &dyn std::fmt::Display| format!("{}: {}", path, e);
+        let mut output =
+            BufWriter::new(File::create(path).map_err(|e| error(&e))?);
+        serde_json::to_writer(&mut output, self).map_err(|e| error(&e))?;
+        output.flush().map_err(|e| error(&e))
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let error = |e: &dyn std::fmt::Display| format!("{}: {}", path, e);
+        let input = BufReader::new(File::open(path).map_err(|e| error(&e))?);
+        let snapshot: Self =
+            serde_json::from_reader(input).map_err(|e| error(&e))?;
+        // Forces could not move a spaceship without mass.
+        let spaceships = &snapshot.world.spaceships;
+        if let Some(massless) = spaceships.values().find(|s| s.mass() <= 0.) {
+            let name = massless.name();
+            return Err(error(&format!("{} needs a positive mass", name)));
+        }
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::clock::Clock;
+    use crate::world::config;
+
+    #[test]
+    fn test_round_trip_is_exact() {
+        let mut world = config::solar();
+        world.clock = Clock::parse("2024-03-20T03:06:00").unwrap();
+        let mut yoshida = integrator::Yoshida4;
+        for _ in 0..10 {
+            yoshida.step(&mut world, 60.);
+            world.time += 60.;
+        }
+        world.update_diagnostics();
+
+        let path = std::env::temp_dir().join("voida-snapshot-test.json");
+        let path = path.to_str().unwrap();
+        Snapshot {
+            world: world.clone(),
+            integrator: yoshida.name(),
+            delta_t: 60.,
+        }
+        .save(path)
+        .unwrap();
+        let snapshot = Snapshot::load(path).unwrap();
+        std::fs::remove_file(path).unwrap();
+
+        assert_eq!(snapshot.integrator, "yoshida4");
+        let mut restored_integrator = snapshot.integrator().unwrap();
+        let mut restored = snapshot.world;
+        assert_eq!(restored.clock.epoch, world.clock.epoch);
+        assert_eq!(restored.time, world.time);
+        let same = |world: &World, restored: &World, epsilon: f64| {
+            let bodies = restored.get_bodies();
+            for (name, body) in world.get_bodies() {
+                assert!(body.pos().equal_to(&bodies[&name].pos(), epsilon));
+                assert!(body.vel().equal_to(&bodies[&name].vel(), epsilon));
+            }
+        };
+        same(&world, &restored, 0.);
+
+        for _ in 0..10 {
+            yoshida.step(&mut world, 60.);
+            restored_integrator.step(&mut restored, 60.);
+        }
+        same(&world, &restored, 0.);
+    }
+}
@@ -1,10 +1,28 @@
 use approx::AbsDiff;
 use nalgebra::Matrix3;
+use serde::{Deserialize, Serialize};
 use std::ops;
 
 pub const G: f64 = 6.6743_f64 * 0.000_000_000_01;
-
-#[derive(Clone, Debug)]
+/// Speed of light in m/s.
+pub const C: f64 = 299_792_458.;
+
+/// First order post-Newtonian correction to the pull of a mass with
+/// gravitational parameter `mu` on a test body at `pos` moving with `vel`,
+/// both relative to the mass, for the Schwarzschild metric in harmonic
+/// coordinates.
+pub fn schwarzschild(mu: f64, pos: &Vec3, vel: &Vec3) -> Vec3 {
+    let NormVec3 {
+        distance,
+        distance_sq,
+        ..
+    } = pos.normalize();
+    let scale = mu / (C * C * distance_sq * distance);
+    pos * (scale * (4. * mu / distance - vel * vel))
+        + &(vel * (scale * 4. * (pos * vel)))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Vec3 {
     pub x: f64,
     pub y: f64,
@@ -41,6 +59,14 @@ impl Vec3 {
         }
     }
 
+    pub fn cross(&self, other: &Vec3) -> Vec3 {
+        Vec3 {
+            x: self.y * other.z - self.z * other.y,
+            y: self.z * other.x - self.x * other.z,
+            z: self.x * other.y - self.y * other.x,
+        }
+    }
+
     pub fn equal_to(&self, other: &Vec3, epsilon: f64) -> bool {
         AbsDiff::default().epsilon(epsilon).eq(&self.x, &other.x)
             && AbsDiff::default().epsilon(epsilon).eq(&self.y, &other.y)
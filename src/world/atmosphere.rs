@@ -0,0 +1,142 @@
+use crate::utils::Vec3;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Atmosphere {
+    /// `(altitude, density)` pairs in m and kg/m³ by increasing altitude.
+    /// Density falls exponentially between them, or linearly next to a
+    /// density of zero, and there is no air above the last one.
+    pub density: Vec<(f64, f64)>,
+    /// Angular velocity the air turns with, in rad/s.
+    pub rotation: Vec3,
+}
+
+impl Atmosphere {
+    pub fn density(&self, altitude: f64) -> f64 {
+        let table = &self.density;
+        let Some(upper) = table.iter().position(|(h, _)| *h > altitude) else {
+            return 0.;
+        };
+        if upper == 0 {
+            return table[0].1;
+        }
+        let (h0, rho0) = table[upper - 1];
+        let (h1, rho1) = table[upper];
+        let fraction = (altitude - h0) / (h1 - h0);
+        if rho0 <= 0. || rho1 <= 0. {
+            return rho0 + (rho1 - rho0) * fraction;
+        }
+        rho0 * (rho1 / rho0).powf(fraction)
+    }
+
+    /// Drag on a body at `offset` from the center of the celestial and
+    /// `altitude` above its surface, moving with `velocity` relative to it.
+    /// `area_per_mass` is the inverse of the ballistic coefficient.
+    pub fn acceleration(
+        &self,
+        offset: &Vec3,
+        altitude: f64,
+        velocity: &Vec3,
+        area_per_mass: f64,
+    ) -> Vec3 {
+        let density = self.density(altitude);
+        if density == 0. {
+            return Vec3::default();
+        }
+
+        let airspeed = velocity - &self.rotation.cross(offset);
+        let speed = airspeed.normalize().distance;
+        airspeed * (-0.5 * density * speed * area_per_mass)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulation::{integrator, Integrator};
+    use crate::utils::G;
+    use crate::world::celestials::{Celestial, Celestials};
+    use crate::world::spaceship::Spaceship;
+    use crate::world::World;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_table_interpolation() {
+        let atmosphere = Atmosphere {
+            density: vec![(0., 1.), (1000., 1e-2)],
+            rotation: Vec3::default(),
+        };
+        assert_eq!(atmosphere.density(-10.), 1.);
+        assert!((atmosphere.density(500.) - 1e-1).abs() < 1e-12);
+        assert_eq!(atmosphere.density(1000.), 0.);
+
+        let atmosphere = Atmosphere {
+            density: vec![(0., 1.), (1000., 0.), (2000., 0.)],
+            rotation: Vec3::default(),
+        };
+        assert_eq!(atmosphere.density(250.), 0.75);
+        assert_eq!(atmosphere.density(1500.), 0.);
+    }
+
+    #[test]
+    fn test_circular_orbit_decay() {
+        let mass = 5.972e24;
+        let radius = 6.371e6;
+        // A single scale height of 58.5 km around 400 km.
+        let atmosphere = Atmosphere {
+            density: vec![(300_000., 2.0e-11), (500_000., 6.8e-13)],
+            rotation: Vec3::default(),
+        };
+        let earth = Celestial::new(
+            "Earth".to_string(),
+            mass,
+            Vec3::default(),
+            Vec3::default(),
+            radius,
+        )
+        .with_atmosphere(atmosphere.clone());
+        let mut celestials = Celestials::new();
+        celestials.add(earth);
+
+        let a_0 = radius + 400_000.;
+        let speed = (G * mass / a_0).sqrt();
+        let spaceship = Spaceship::new(
+            "ISS".to_string(),
+            420_000.,
+            Vec3 {
+                x: a_0,
+                y: 0.,
+                z: 0.,
+            },
+            Vec3 {
+                x: 0.,
+                y: speed,
+                z: 0.,
+            },
+        )
+        .with_drag(1000., 2.2);
+        let area_per_mass = 1. / spaceship.ballistic_coefficient();
+        let mut spaceships = BTreeMap::new();
+        spaceships.insert(spaceship.name(), spaceship);
+        let mut world = World::new(celestials, spaceships);
+
+        let days = 5.;
+        let delta_t = 5.;
+        let mut yoshida = integrator::Yoshida4;
+        for _ in 0..(days * 86_400. / delta_t) as usize {
+            yoshida.step(&mut world, delta_t);
+        }
+
+        let ship = &world.spaceships["ISS"];
+        let energy = (&ship.vel() * &ship.vel()) / 2.
+            - G * mass / ship.pos().normalize().distance;
+        let a = -G * mass / (2. * energy);
+
+        // da/dt = -ρ(a) √(μa) C_d A / m for a circular orbit.
+        let density = atmosphere.density(a_0 - radius);
+        let expected =
+            -density * (G * mass * a_0).sqrt() * area_per_mass * days * 86_400.;
+        let error = ((a - a_0 - expected) / expected).abs();
+        assert!(error < 2e-2, "decay {}, expected {expected}", a - a_0);
+    }
+}
@@ -0,0 +1,198 @@
+use crate::utils::Vec3;
+use nalgebra::{Matrix3, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Largest rotation in rad during one substep of the attitude integration.
+const MAX_ANGLE: f64 = 0.05;
+/// Substeps per step at most, beyond which they turn further than
+/// `MAX_ANGLE`, so that rails at high time speed stay affordable.
+const MAX_SUBSTEPS: f64 = 10_000.;
+
+/// Unit quaternion `w + x i + y j + z k`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Quaternion {
+    pub w: f64,
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl Quaternion {
+    pub fn identity() -> Self {
+        Self {
+            w: 1.,
+            x: 0.,
+            y: 0.,
+            z: 0.,
+        }
+    }
+
+    /// `v` turned by the rotation this quaternion stands for.
+    pub fn rotate(&self, v: &Vec3) -> Vec3 {
+        // v + 2 w (u x v) + 2 u x (u x v) with u the vector part.
+        let u = Vec3 {
+            x: self.x,
+            y: self.y,
+            z: self.z,
+        };
+        let t = u.cross(v) * 2.;
+        v.clone() + &(&t * self.w) + &u.cross(&t)
+    }
+}
+
+/// Orientation and rotation of a rigid body. Vectors other than the
+/// orientation are in body coordinates.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Attitude {
+    /// Rotates body coordinates into inertial ones.
+    pub orientation: Quaternion,
+    /// In rad/s.
+    pub angular_velocity: Vec3,
+    /// Inertia tensor about the center of mass in kg m².
+    pub inertia: [[f64; 3]; 3],
+    /// External torque in N m, held until it is changed.
+    pub torque: Vec3,
+}
+
+impl Attitude {
+    /// At rest and lined up with the inertial axes.
+    pub fn new(inertia: [[f64; 3]; 3]) -> Self {
+        Self {
+            orientation: Quaternion::identity(),
+            angular_velocity: Vec3::default(),
+            inertia,
+            torque: Vec3::default(),
+        }
+    }
+
+    /// Integrates Euler's rotational equations `I dω/dt + ω × I ω = τ`
+    /// together with the quaternion kinematics `dq/dt = q ω / 2` through
+    /// `delta_t` with fourth order Runge-Kutta substeps. The orientation is
+    /// renormalized after every substep.
+    pub fn step(&mut self, delta_t: f64) {
+        let inertia = Matrix3::from_fn(|i, j| self.inertia[i][j]);
+        let Some(inverse) = inertia.try_inverse() else {
+            return;
+        };
+        let torque = vector(&self.torque);
+        let derivative = |state: &[f64; 7]| {
+            let [w, x, y, z, p, q, r] = *state;
+            let omega = Vector3::new(p, q, r);
+            let alpha = inverse * (torque - omega.cross(&(inertia * omega)));
+            [
+                -0.5 * (x * p + y * q + z * r),
+                0.5 * (w * p + y * r - z * q),
+                0.5 * (w * q + z * p - x * r),
+                0.5 * (w * r + x * q - y * p),
+                alpha.x,
+                alpha.y,
+                alpha.z,
+            ]
+        };
+
+        // Roughly the spin at the end of the step.
+        let spin = vector(&self.angular_velocity).norm()
+            + (inverse * torque).norm() * delta_t.abs();
+        let turn = spin * delta_t.abs();
+        let substeps = (turn / MAX_ANGLE).ceil().clamp(1., MAX_SUBSTEPS);
+        let h = delta_t / substeps;
+        let Quaternion { w, x, y, z } = self.orientation;
+        let Vec3 { x: p, y: q, z: r } = self.angular_velocity;
+        let mut state = [w, x, y, z, p, q, r];
+        let along = |state: &[f64; 7], k: &[f64; 7], scale: f64| {
+            std::array::from_fn(|i| state[i] + k[i] * scale)
+        };
+        for _ in 0..substeps as u64 {
+            let k1 = derivative(&state);
+            let k2 = derivative(&along(&state, &k1, h / 2.));
+            let k3 = derivative(&along(&state, &k2, h / 2.));
+            let k4 = derivative(&along(&state, &k3, h));
+            for i in 0..7 {
+                state[i] += h / 6. * (k1[i] + 2. * k2[i] + 2. * k3[i] + k4[i]);
+            }
+            let norm = state[..4].iter().map(|c| c * c).sum::<f64>().sqrt();
+            state[..4].iter_mut().for_each(|c| *c /= norm);
+        }
+
+        let [w, x, y, z, p, q, r] = state;
+        self.orientation = Quaternion { w, x, y, z };
+        self.angular_velocity = Vec3 { x: p, y: q, z: r };
+    }
+}
+
+fn vector(v: &Vec3) -> Vector3<f64> {
+    Vector3::new(v.x, v.y, v.z)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Angular momentum in inertial coordinates and rotational energy.
+    fn invariants(attitude: &Attitude) -> (Vec3, f64) {
+        let inertia = Matrix3::from_fn(|i, j| attitude.inertia[i][j]);
+        let omega = vector(&attitude.angular_velocity);
+        let momentum = inertia * omega;
+        let momentum = Vec3 {
+            x: momentum.x,
+            y: momentum.y,
+            z: momentum.z,
+        };
+        (
+            attitude.orientation.rotate(&momentum),
+            0.5 * omega.dot(&(inertia * omega)),
+        )
+    }
+
+    #[test]
+    fn test_tumbling_about_the_intermediate_axis() {
+        let mut attitude =
+            Attitude::new([[1., 0., 0.], [0., 2., 0.], [0., 0., 3.]]);
+        attitude.angular_velocity = Vec3 {
+            x: 0.01,
+            y: 1.,
+            z: 0.01,
+        };
+        let (momentum, energy) = invariants(&attitude);
+
+        let mut flipped = false;
+        for _ in 0..1000 {
+            attitude.step(0.1);
+            flipped |= attitude.angular_velocity.y < 0.;
+        }
+        // Free rotation about the intermediate axis is unstable and keeps
+        // turning over, while momentum and energy stay put.
+        assert!(flipped);
+        let (end_momentum, end_energy) = invariants(&attitude);
+        assert!(end_momentum.equal_to(&momentum, 1e-6), "{end_momentum:?}");
+        assert!((end_energy - energy).abs() < 1e-6, "{end_energy}");
+    }
+
+    #[test]
+    fn test_constant_torque_spins_up() {
+        let mut attitude =
+            Attitude::new([[2., 0., 0.], [0., 2., 0.], [0., 0., 2.]]);
+        attitude.torque = Vec3 {
+            x: 0.,
+            y: 0.,
+            z: 1.,
+        };
+        for _ in 0..10 {
+            attitude.step(1.);
+        }
+
+        assert!((attitude.angular_velocity.z - 5.).abs() < 1e-9);
+        // Turned by alpha t^2 / 2 = 25 rad about z.
+        let nose = attitude.orientation.rotate(&Vec3 {
+            x: 1.,
+            y: 0.,
+            z: 0.,
+        });
+        let expected = Vec3 {
+            x: 25_f64.cos(),
+            y: 25_f64.sin(),
+            z: 0.,
+        };
+        assert!(nose.equal_to(&expected, 1e-6), "{nose:?}");
+    }
+}
@@ -1,22 +1,40 @@
-use crate::utils::{NormVec3, Vec3, G};
-use std::collections::HashMap;
+use super::atmosphere::Atmosphere;
+use super::harmonics::Harmonics;
+use super::radiation;
+use super::rotation::{Ellipsoid, Rotation};
+use super::spaceship::Spaceship;
+use crate::utils::{schwarzschild, NormVec3, Vec3, G};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Clone, Debug)]
-pub struct Celestials(HashMap<String, Celestial>);
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Celestials(BTreeMap<String, Celestial>);
 
 impl Celestials {
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self(BTreeMap::new())
     }
 
     pub fn add(&mut self, new_celestial: Celestial) {
         self.0.insert(new_celestial.name(), new_celestial);
     }
 
-    pub fn get(&self) -> HashMap<String, Celestial> {
+    pub fn get(&self) -> BTreeMap<String, Celestial> {
         self.0.clone()
     }
 
+    pub fn get_ref(&self, name: &str) -> Option<&Celestial> {
+        self.0.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Celestial> {
+        self.0.get_mut(name)
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.0.remove(name);
+    }
+
     pub fn get_global_acceleration(&self, origin: Vec3) -> Vec3 {
         let mut acceleration = Vec3::default();
 
@@ -35,23 +53,113 @@ impl Celestials {
         acceleration
     }
 
-    pub fn update(&mut self, delta_t: f64) {
-        let old_world = self.clone();
+    /// Pull of the non-spherical part of every celestial's field on a point,
+    /// on top of [`Self::get_global_acceleration`].
+    pub fn get_harmonic_acceleration(&self, origin: &Vec3) -> Vec3 {
+        let mut acceleration = Vec3::default();
+        for celestial in self.0.values() {
+            if let Some(harmonics) = celestial.harmonics() {
+                acceleration += harmonics.acceleration(
+                    celestial.mass(),
+                    &(origin - &celestial.pos()),
+                );
+            }
+        }
+        acceleration
+    }
 
-        for celestial in self.0.values_mut() {
-            let a = old_world.get_global_acceleration(celestial.pos());
-            celestial.apply_gravity(a, delta_t);
+    /// Post-Newtonian correction to the pull of every celestial on a body
+    /// at `pos` moving with `vel`.
+    pub fn get_relativistic_acceleration(
+        &self,
+        pos: &Vec3,
+        vel: &Vec3,
+    ) -> Vec3 {
+        let mut acceleration = Vec3::default();
+        for celestial in self.0.values() {
+            let offset = pos - &celestial.pos();
+            if offset.normalize().distance_sq > 1. {
+                acceleration += schwarzschild(
+                    G * celestial.mass(),
+                    &offset,
+                    &(vel - &celestial.vel()),
+                );
+            }
         }
+        acceleration
+    }
+
+    /// Drag of every atmosphere on a spaceship, none without a drag area or
+    /// a mass, as a snapshot may hold.
+    pub fn get_drag_acceleration(&self, spaceship: &Spaceship) -> Vec3 {
+        let mut acceleration = Vec3::default();
+        let area_per_mass = 1. / spaceship.ballistic_coefficient();
+        if area_per_mass == 0. || !area_per_mass.is_finite() {
+            return acceleration;
+        }
+        for celestial in self.0.values() {
+            if let Some(atmosphere) = celestial.atmosphere() {
+                let offset = spaceship.pos() - &celestial.pos();
+                let altitude = offset.normalize().distance - celestial.rad();
+                acceleration += atmosphere.acceleration(
+                    &offset,
+                    altitude,
+                    &(spaceship.vel() - &celestial.vel()),
+                    area_per_mass,
+                );
+            }
+        }
+        acceleration
+    }
+
+    /// Radiation pressure of every luminous celestial on a spaceship, unless
+    /// another celestial shadows it. None without an area or a mass.
+    pub fn get_radiation_acceleration(&self, spaceship: &Spaceship) -> Vec3 {
+        let mut acceleration = Vec3::default();
+        let area_per_mass = spaceship.radiation_area() / spaceship.mass();
+        if area_per_mass == 0. || !area_per_mass.is_finite() {
+            return acceleration;
+        }
+        for source in self.0.values() {
+            if let Some(luminosity) = source.luminosity() {
+                let occulters =
+                    self.0.values().filter(|c| c.name != source.name);
+                acceleration += radiation::acceleration(
+                    source,
+                    luminosity,
+                    &spaceship.pos(),
+                    area_per_mass,
+                    spaceship.reflectivity(),
+                    occulters,
+                );
+            }
+        }
+        acceleration
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Celestial> {
+        self.0.values()
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Celestial> {
+        self.0.values_mut()
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Celestial {
     name: String,
     mass: f64,
     pos: Vec3,
     vel: Vec3,
     rad: f64,
+    harmonics: Option<Harmonics>,
+    atmosphere: Option<Atmosphere>,
+    /// Radiated power in W.
+    luminosity: Option<f64>,
+    rotation: Option<Rotation>,
+    /// Shape for geodetic coordinates.
+    ellipsoid: Option<Ellipsoid>,
 }
 
 impl Celestial {
@@ -68,9 +176,19 @@ impl Celestial {
             pos,
             vel,
             rad,
+            harmonics: None,
+            atmosphere: None,
+            luminosity: None,
+            rotation: None,
+            ellipsoid: None,
         }
     }
 
+    pub fn with_harmonics(mut self, harmonics: Harmonics) -> Self {
+        self.harmonics = Some(harmonics);
+        self
+    }
+
     pub fn name(&self) -> String {
         self.name.clone()
     }
@@ -91,8 +209,62 @@ impl Celestial {
         self.rad
     }
 
-    pub fn apply_gravity(&mut self, acceleration: Vec3, delta_t: f64) {
+    pub fn harmonics(&self) -> Option<&Harmonics> {
+        self.harmonics.as_ref()
+    }
+
+    pub fn with_atmosphere(mut self, atmosphere: Atmosphere) -> Self {
+        self.atmosphere = Some(atmosphere);
+        self
+    }
+
+    pub fn atmosphere(&self) -> Option<&Atmosphere> {
+        self.atmosphere.as_ref()
+    }
+
+    pub fn with_luminosity(mut self, luminosity: f64) -> Self {
+        self.luminosity = Some(luminosity);
+        self
+    }
+
+    pub fn luminosity(&self) -> Option<f64> {
+        self.luminosity
+    }
+
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = Some(rotation);
+        self
+    }
+
+    pub fn rotation(&self) -> Option<&Rotation> {
+        self.rotation.as_ref()
+    }
+
+    pub fn with_ellipsoid(mut self, ellipsoid: Ellipsoid) -> Self {
+        self.ellipsoid = Some(ellipsoid);
+        self
+    }
+
+    pub fn ellipsoid(&self) -> Option<&Ellipsoid> {
+        self.ellipsoid.as_ref()
+    }
+
+    /// Takes over `mass` more and grows to `rad`, keeping everything else.
+    pub fn grow(&mut self, mass: f64, rad: f64) {
+        self.mass += mass;
+        self.rad = rad;
+    }
+
+    pub fn kick(&mut self, acceleration: Vec3, delta_t: f64) {
         self.vel += acceleration * delta_t;
+    }
+
+    pub fn drift(&mut self, delta_t: f64) {
         self.pos += &self.vel * delta_t;
     }
+
+    pub fn set_state(&mut self, pos: Vec3, vel: Vec3) {
+        self.pos = pos;
+        self.vel = vel;
+    }
 }
@@ -0,0 +1,267 @@
+use serde::{Deserialize, Serialize};
+use std::f64::consts::PI;
+
+/// Julian date of J2000.0, 2000-01-01 12:00:00 TT.
+pub const J2000: f64 = 2_451_545.;
+/// TT - TAI in s.
+const TT_MINUS_TAI: f64 = 32.184;
+/// Days from 1970-01-01 to 2000-01-01.
+const DAYS_TO_2000: i64 = 10_957;
+
+/// UTC dates from which TAI - UTC took the given number of seconds. Before
+/// 1972 UTC did not step by whole seconds, and that era is treated as if it
+/// already ran 10 s behind TAI.
+const LEAP_SECONDS: [(i64, u32, f64); 28] = [
+    (1972, 1, 10.),
+    (1972, 7, 11.),
+    (1973, 1, 12.),
+    (1974, 1, 13.),
+    (1975, 1, 14.),
+    (1976, 1, 15.),
+    (1977, 1, 16.),
+    (1978, 1, 17.),
+    (1979, 1, 18.),
+    (1980, 1, 19.),
+    (1981, 7, 20.),
+    (1982, 7, 21.),
+    (1983, 7, 22.),
+    (1985, 7, 23.),
+    (1988, 1, 24.),
+    (1990, 1, 25.),
+    (1991, 1, 26.),
+    (1992, 7, 27.),
+    (1993, 7, 28.),
+    (1994, 7, 29.),
+    (1996, 1, 30.),
+    (1997, 7, 31.),
+    (1999, 1, 32.),
+    (2006, 1, 33.),
+    (2009, 1, 34.),
+    (2012, 7, 35.),
+    (2015, 7, 36.),
+    (2017, 1, 37.),
+];
+
+/// Absolute time of the simulation. [`World::time`](super::World::time)
+/// counts TT seconds from `epoch`, and the clock turns that into the other
+/// time scales. All of them are given as seconds since J2000.0 in their own
+/// scale, UTC without the leap seconds.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Clock {
+    /// TT seconds since J2000.0 at the start of the simulation.
+    pub epoch: f64,
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Self { epoch: 0. }
+    }
+}
+
+impl Clock {
+    /// Starts at a UTC date given as `YYYY-MM-DDTHH:MM:SS`, with optional
+    /// fractional seconds and a trailing `Z`.
+    pub fn parse(date: &str) -> Result<Self, String> {
+        let error = || {
+            format!("Expected a date like 2024-03-20T03:06:00, got {}", date)
+        };
+        let (day, time) = date
+            .trim_end_matches('Z')
+            .split_once(['T', ' '])
+            .ok_or_else(error)?;
+        let day: Vec<&str> = day.split('-').collect();
+        let time: Vec<&str> = time.split(':').collect();
+        let [year, month, day] = day[..] else {
+            return Err(error());
+        };
+        let [hour, minute, second] = time[..] else {
+            return Err(error());
+        };
+
+        let year = year.parse().map_err(|_| error())?;
+        let month: u32 = month.parse().map_err(|_| error())?;
+        let day: u32 = day.parse().map_err(|_| error())?;
+        let hour: u32 = hour.parse().map_err(|_| error())?;
+        let minute: u32 = minute.parse().map_err(|_| error())?;
+        let second: f64 = second.parse().map_err(|_| error())?;
+        if !(1..=12).contains(&month)
+            || day < 1
+            // Days past the end of the month roll over into the next one.
+            || civil(days(year, month, day)) != (year, month, day)
+            || hour > 23
+            || minute > 59
+            || !(0. ..61.).contains(&second)
+        {
+            return Err(error());
+        }
+
+        // Only the last minute of a day that ends in a leap second has 61.
+        let midnight = utc_seconds(year, month, day);
+        let leap = tai_minus_utc(midnight + 86_400.) > tai_minus_utc(midnight);
+        if second >= 60. && !(leap && hour == 23 && minute == 59) {
+            return Err(error());
+        }
+
+        // Counting the seconds of the day keeps 23:59:60 apart from the
+        // following midnight.
+        let tai = midnight
+            + tai_minus_utc(midnight)
+            + (hour * 3600 + minute * 60) as f64
+            + second;
+        Ok(Self {
+            epoch: tai + TT_MINUS_TAI,
+        })
+    }
+
+    pub fn tt(&self, time: f64) -> f64 {
+        self.epoch + time
+    }
+
+    pub fn tai(&self, time: f64) -> f64 {
+        self.tt(time) - TT_MINUS_TAI
+    }
+
+    /// TDB with the two largest periodic terms, good to about 30 µs.
+    pub fn tdb(&self, time: f64) -> f64 {
+        let tt = self.tt(time);
+        let g = (357.53 + 0.985_600_28 * tt / 86_400.) * PI / 180.;
+        tt + 0.001_657 * g.sin() + 0.000_014 * (2. * g).sin()
+    }
+
+    /// UTC, which during a leap second already counts the first second of
+    /// the next day.
+    pub fn utc(&self, time: f64) -> f64 {
+        let tai = self.tai(time);
+        let offset = LEAP_SECONDS
+            .iter()
+            .rev()
+            .map(|&(year, month, offset)| {
+                (utc_seconds(year, month, 1) + offset, offset)
+            })
+            .find(|(start, _)| tai >= *start)
+            .map_or(LEAP_SECONDS[0].2, |(_, offset)| offset);
+        tai - offset
+    }
+
+    /// UTC calendar date like `2016-12-31 23:59:60.250 UTC`.
+    pub fn date(&self, time: f64) -> String {
+        let utc = self.utc(time);
+        // Inside a leap second UTC stands still at the next midnight.
+        let leap = self.utc(time + 1.) - utc < 0.5;
+        let utc = if leap { utc - 1. } else { utc };
+
+        let seconds = utc + 43_200.;
+        let days = (seconds / 86_400.).floor();
+        let (year, month, day) = civil(days as i64 + DAYS_TO_2000);
+        let second_of_day = seconds - days * 86_400.;
+        let hour = (second_of_day / 3600.).floor();
+        let minute = ((second_of_day - hour * 3600.) / 60.).floor();
+        let mut second = second_of_day - hour * 3600. - minute * 60.;
+        if leap {
+            second += 1.;
+        }
+        format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:06.3} UTC",
+            year, month, day, hour, minute, second
+        )
+    }
+}
+
+/// Julian date of `seconds` since J2000.0 in any of the time scales.
+pub fn julian_date(seconds: f64) -> f64 {
+    J2000 + seconds / 86_400.
+}
+
+/// UTC seconds since J2000.0 at the midnight starting the given day.
+fn utc_seconds(year: i64, month: u32, day: u32) -> f64 {
+    (days(year, month, day) - DAYS_TO_2000) as f64 * 86_400. - 43_200.
+}
+
+/// TAI - UTC on a UTC date in seconds since J2000.0.
+fn tai_minus_utc(utc: f64) -> f64 {
+    LEAP_SECONDS
+        .iter()
+        .rev()
+        .find(|&&(year, month, _)| utc >= utc_seconds(year, month, 1))
+        .map_or(LEAP_SECONDS[0].2, |&(_, _, offset)| offset)
+}
+
+/// Days since 1970-01-01 of a date in the proleptic Gregorian calendar.
+fn days(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5
+        + day as i64
+        - 1;
+    let day_of_era =
+        year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Inverse of [`days`].
+fn civil(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524
+        - day_of_era / 146_096)
+        / 365;
+    let day_of_year =
+        day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let m = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * m + 2) / 5 + 1) as u32;
+    let month = if m < 10 { m + 3 } else { m - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_j2000() {
+        let clock = Clock::default();
+        assert_eq!(julian_date(clock.tt(0.)), J2000);
+        assert_eq!(clock.tai(0.), -32.184);
+        assert_eq!(clock.date(0.), "2000-01-01 11:58:55.816 UTC");
+        assert!(clock.tdb(0.).abs() < 1e-4);
+
+        let parsed = Clock::parse("2000-01-01T11:58:55.816Z").unwrap();
+        assert!((parsed.epoch - clock.epoch).abs() < 1e-6);
+        assert!(Clock::parse("2000-13-01T00:00:00").is_err());
+        assert!(Clock::parse("2024-02-31T00:00:00").is_err());
+        assert!(Clock::parse("2023-02-29T00:00:00").is_err());
+        assert!(Clock::parse("2024-02-29T00:00:00").is_ok());
+        assert!(Clock::parse("2024-04-31T00:00:00").is_err());
+        assert!(Clock::parse("2016-12-31T23:59:60").is_ok());
+        assert!(Clock::parse("2016-12-30T23:59:60").is_err());
+        assert!(Clock::parse("2016-12-31T23:58:60").is_err());
+    }
+
+    #[test]
+    fn test_leap_second() {
+        let clock = Clock::parse("2016-12-31T23:59:59").unwrap();
+        let after = Clock::parse("2017-01-01T00:00:00").unwrap();
+        assert_eq!(after.epoch - clock.epoch, 2.);
+
+        assert_eq!(clock.date(0.), "2016-12-31 23:59:59.000 UTC");
+        assert_eq!(clock.date(1.25), "2016-12-31 23:59:60.250 UTC");
+        assert_eq!(clock.date(2.), "2017-01-01 00:00:00.000 UTC");
+        assert_eq!(clock.tai(2.) - clock.utc(2.), 37.);
+        assert_eq!(clock.tai(-86_400.) - clock.utc(-86_400.), 36.);
+    }
+
+    #[test]
+    fn test_tdb_stays_close_to_tt() {
+        let clock = Clock::parse("2024-03-20T03:06:00").unwrap();
+        let mut largest: f64 = 0.;
+        for day in 0..366 {
+            let time = day as f64 * 86_400.;
+            largest = largest.max((clock.tdb(time) - clock.tt(time)).abs());
+        }
+        assert!(largest > 1.6e-3 && largest < 1.7e-3, "{largest}");
+    }
+}
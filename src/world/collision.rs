@@ -0,0 +1,414 @@
+use super::celestials::Celestial;
+use super::World;
+use crate::utils::Vec3;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Newest collisions kept on [`World::collisions`].
+const MAX_EVENTS: usize = 8;
+
+/// What happens when a body touches a celestial.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum Outcome {
+    /// Bodies fly through each other and nothing is reported.
+    Ignore,
+    /// The simulation pauses on the impact.
+    Stop,
+    /// The spaceship, or the lighter of two celestials, disappears.
+    Remove,
+    /// The bodies become one with their total mass and momentum.
+    Merge,
+    /// The bodies bounce off each other elastically.
+    Bounce,
+}
+
+impl Outcome {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "ignore" => Some(Outcome::Ignore),
+            "stop" => Some(Outcome::Stop),
+            "remove" => Some(Outcome::Remove),
+            "merge" => Some(Outcome::Merge),
+            "bounce" => Some(Outcome::Bounce),
+            _ => None,
+        }
+    }
+}
+
+/// Outcomes of spaceship impacts and of celestials overlapping, set per
+/// scenario.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CollisionPolicy {
+    pub spaceships: Outcome,
+    pub celestials: Outcome,
+}
+
+impl Default for CollisionPolicy {
+    fn default() -> Self {
+        Self {
+            spaceships: Outcome::Ignore,
+            celestials: Outcome::Ignore,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Collision {
+    pub time: f64,
+    pub body: String,
+    pub target: String,
+    /// Relative speed along the line between the centers.
+    pub speed: f64,
+    pub outcome: Outcome,
+}
+
+impl fmt::Display for Collision {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:.0} s: {} hit {} at {:.0} m/s, {:?}",
+            self.time, self.body, self.target, self.speed, self.outcome
+        )
+    }
+}
+
+impl World {
+    /// Applies the collision policy to every spaceship below a celestial's
+    /// radius and every pair of overlapping celestials, and records each
+    /// impact in `collisions`. Returns whether an outcome asks to stop.
+    /// Bodies that stopped the simulation stay where they are, so they only
+    /// stop it again after they came apart and touch anew.
+    pub fn resolve_collisions(&mut self) -> bool {
+        let mut stop = false;
+        let mut contacts = Vec::new();
+        let outcome = self.collision_policy.spaceships;
+        if outcome != Outcome::Ignore {
+            for (body, target) in self.spaceship_impacts() {
+                if outcome == Outcome::Stop {
+                    if self.touched(&body, &target, &mut contacts) {
+                        continue;
+                    }
+                    stop = true;
+                }
+                self.resolve_spaceship(&body, &target, outcome);
+            }
+        }
+        let outcome = self.collision_policy.celestials;
+        if outcome != Outcome::Ignore {
+            for (body, target) in self.celestial_overlaps() {
+                if outcome == Outcome::Stop {
+                    if self.touched(&body, &target, &mut contacts) {
+                        continue;
+                    }
+                    stop = true;
+                }
+                self.resolve_celestials(&body, &target, outcome);
+            }
+        }
+        self.contacts = contacts;
+        stop
+    }
+
+    /// Adds `body` touching `target` to `contacts` and returns whether they
+    /// touched already after the previous step.
+    fn touched(
+        &self,
+        body: &str,
+        target: &str,
+        contacts: &mut Vec<(String, String)>,
+    ) -> bool {
+        let contact = (body.to_string(), target.to_string());
+        let touched = self.contacts.contains(&contact);
+        contacts.push(contact);
+        touched
+    }
+
+    fn spaceship_impacts(&self) -> Vec<(String, String)> {
+        let mut impacts = Vec::new();
+        for spaceship in self.spaceships.values() {
+            let celestial = self.celestials.values().find(|c| {
+                (spaceship.pos() - &c.pos()).normalize().distance < c.rad()
+            });
+            if let Some(celestial) = celestial {
+                impacts.push((spaceship.name(), celestial.name()));
+            }
+        }
+        impacts
+    }
+
+    /// Sweeps along the x axis, so that only celestials whose extents along
+    /// it overlap are compared. Each pair is named in alphabetical order.
+    fn celestial_overlaps(&self) -> Vec<(String, String)> {
+        let left = |c: &Celestial| c.pos().x - c.rad();
+        let mut celestials: Vec<&Celestial> =
+            self.celestials.values().collect();
+        celestials.sort_by(|a, b| left(a).total_cmp(&left(b)));
+        let mut overlaps = Vec::new();
+        for (i, a) in celestials.iter().enumerate() {
+            let right = a.pos().x + a.rad();
+            for b in &celestials[i + 1..] {
+                if left(b) >= right {
+                    break;
+                }
+                let distance = (a.pos() - &b.pos()).normalize().distance;
+                if distance < a.rad() + b.rad() {
+                    let (a, b) = (a.name(), b.name());
+                    overlaps.push(if a < b { (a, b) } else { (b, a) });
+                }
+            }
+        }
+        overlaps
+    }
+
+    fn record(
+        &mut self,
+        body: &str,
+        target: &str,
+        speed: f64,
+        outcome: Outcome,
+    ) {
+        if self.collisions.len() == MAX_EVENTS {
+            self.collisions.remove(0);
+        }
+        self.collisions.push(Collision {
+            time: self.time,
+            body: body.to_string(),
+            target: target.to_string(),
+            speed,
+            outcome,
+        });
+    }
+
+    fn resolve_spaceship(
+        &mut self,
+        body: &str,
+        target: &str,
+        outcome: Outcome,
+    ) {
+        let Some(spaceship) = self.spaceships.get(body).cloned() else {
+            return;
+        };
+        let Some(celestial) = self.celestials.get_ref(target).cloned() else {
+            return;
+        };
+
+        let normal = (spaceship.pos() - &celestial.pos()).normalize();
+        let relative = spaceship.vel() - &celestial.vel();
+        let speed = -(&relative * &normal.unit_direction);
+        self.record(body, target, speed, outcome);
+
+        match outcome {
+            Outcome::Ignore | Outcome::Stop => {}
+            Outcome::Remove => {
+                self.spaceships.remove(body);
+            }
+            Outcome::Merge => {
+                self.spaceships.remove(body);
+                self.celestials.add(merge(
+                    &celestial,
+                    (spaceship.mass(), spaceship.pos(), spaceship.vel(), 0.),
+                ));
+            }
+            Outcome::Bounce => {
+                let n = normal.unit_direction;
+                let pos = &celestial.pos() + &n * celestial.rad();
+                let vel = if speed > 0. {
+                    spaceship.vel() + &(n * (2. * speed))
+                } else {
+                    spaceship.vel()
+                };
+                if let Some(spaceship) = self.spaceships.get_mut(body) {
+                    spaceship.set_state(pos, vel);
+                }
+            }
+        }
+    }
+
+    fn resolve_celestials(
+        &mut self,
+        body: &str,
+        target: &str,
+        outcome: Outcome,
+    ) {
+        let (Some(a), Some(b)) = (
+            self.celestials.get_ref(body).cloned(),
+            self.celestials.get_ref(target).cloned(),
+        ) else {
+            return;
+        };
+
+        let normal = (a.pos() - &b.pos()).normalize();
+        let relative = a.vel() - &b.vel();
+        let speed = -(&relative * &normal.unit_direction);
+        self.record(body, target, speed, outcome);
+
+        let (heavy, light) = if a.mass() >= b.mass() { (a, b) } else { (b, a) };
+        match outcome {
+            Outcome::Ignore | Outcome::Stop => {}
+            Outcome::Remove => self.celestials.remove(&light.name()),
+            Outcome::Merge => {
+                self.celestials.remove(&light.name());
+                self.celestials.add(merge(
+                    &heavy,
+                    (light.mass(), light.pos(), light.vel(), light.rad()),
+                ));
+            }
+            Outcome::Bounce => {
+                let total = heavy.mass() + light.mass();
+                // Unit vector from the light body to the heavy one.
+                let n = (heavy.pos() - &light.pos()).normalize().unit_direction;
+                let approach = &(light.vel() - &heavy.vel()) * &n;
+                let overlap = heavy.rad() + light.rad()
+                    - (heavy.pos() - &light.pos()).normalize().distance;
+
+                let mut heavy_state = (heavy.pos(), heavy.vel());
+                let mut light_state = (light.pos(), light.vel());
+                if approach > 0. {
+                    let impulse = 2. * approach / total;
+                    heavy_state.1 += &n * (impulse * light.mass());
+                    light_state.1 += &n * (-impulse * heavy.mass());
+                }
+                // Push both apart along the normal, keeping the barycenter.
+                heavy_state.0 += &n * (overlap * light.mass() / total);
+                light_state.0 += &n * (-overlap * heavy.mass() / total);
+
+                for (name, (pos, vel)) in
+                    [(heavy.name(), heavy_state), (light.name(), light_state)]
+                {
+                    if let Some(celestial) = self.celestials.get_mut(&name) {
+                        celestial.set_state(pos, vel);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// `celestial` after swallowing a body given as `(mass, pos, vel, rad)`,
+/// keeping the total momentum and volume.
+fn merge(
+    celestial: &Celestial,
+    (mass, pos, vel, rad): (f64, Vec3, Vec3, f64),
+) -> Celestial {
+    let total = celestial.mass() + mass;
+    let mut merged = celestial.clone();
+    merged.set_state(
+        (celestial.pos() * celestial.mass() + &(pos * mass)) / total,
+        (celestial.vel() * celestial.mass() + &(vel * mass)) / total,
+    );
+    merged.grow(mass, (celestial.rad().powi(3) + rad.powi(3)).cbrt());
+    merged
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::celestials::Celestials;
+    use crate::world::spaceship::Spaceship;
+    use std::collections::BTreeMap;
+
+    fn setup(policy: CollisionPolicy) -> World {
+        let mut celestials = Celestials::new();
+        celestials.add(Celestial::new(
+            "A".to_string(),
+            3e24,
+            Vec3::default(),
+            Vec3 {
+                x: 1000.,
+                y: 0.,
+                z: 0.,
+            },
+            6e6,
+        ));
+        celestials.add(Celestial::new(
+            "B".to_string(),
+            1e24,
+            Vec3 {
+                x: 1e7,
+                y: 0.,
+                z: 0.,
+            },
+            Vec3 {
+                x: -2000.,
+                y: 500.,
+                z: 0.,
+            },
+            5e6,
+        ));
+        let spaceship = Spaceship::new(
+            "Ship".to_string(),
+            1e5,
+            Vec3 {
+                x: 0.,
+                y: 5.9e6,
+                z: 0.,
+            },
+            Vec3 {
+                x: 0.,
+                y: -100.,
+                z: 0.,
+            },
+        );
+        let mut spaceships = BTreeMap::new();
+        spaceships.insert(spaceship.name(), spaceship);
+
+        let mut world = World::new(celestials, spaceships);
+        world.collision_policy = policy;
+        world
+    }
+
+    fn momentum(world: &World) -> Vec3 {
+        let mut momentum = Vec3::default();
+        for celestial in world.celestials.values() {
+            momentum += celestial.vel() * celestial.mass();
+        }
+        momentum
+    }
+
+    #[test]
+    fn test_outcomes() {
+        let mut world = setup(CollisionPolicy {
+            spaceships: Outcome::Remove,
+            celestials: Outcome::Merge,
+        });
+        let momentum_0 = momentum(&world);
+        assert!(!world.resolve_collisions());
+        assert!(world.spaceships.is_empty());
+        let merged = world.celestials.get();
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged["A"].mass(), 4e24);
+        assert!(momentum(&world).equal_to(&momentum_0, 1e12));
+        assert_eq!(world.collisions.len(), 2);
+
+        let mut world = setup(CollisionPolicy {
+            spaceships: Outcome::Bounce,
+            celestials: Outcome::Bounce,
+        });
+        let momentum_0 = momentum(&world);
+        assert!(!world.resolve_collisions());
+        let ship = &world.spaceships["Ship"];
+        assert!(ship.vel().y > 0.);
+        let bodies = world.celestials.get();
+        let distance = (bodies["A"].pos() - &bodies["B"].pos()).normalize();
+        assert!(distance.distance >= 11e6 * (1. - 1e-12));
+        assert!(momentum(&world).equal_to(&momentum_0, 1e12));
+
+        let mut world = setup(CollisionPolicy {
+            spaceships: Outcome::Stop,
+            celestials: Outcome::Ignore,
+        });
+        assert!(world.resolve_collisions());
+        assert_eq!(world.collisions.len(), 1);
+        // Still inside, which is no new impact.
+        assert!(!world.resolve_collisions());
+        assert_eq!(world.collisions.len(), 1);
+        let ship = world.spaceships.get_mut("Ship").unwrap();
+        let (pos, vel) = (ship.pos(), ship.vel());
+        ship.set_state(pos.clone() * 2., vel.clone());
+        assert!(!world.resolve_collisions());
+        let ship = world.spaceships.get_mut("Ship").unwrap();
+        ship.set_state(pos, vel);
+        assert!(world.resolve_collisions());
+        assert_eq!(world.collisions.len(), 2);
+    }
+}
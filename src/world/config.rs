@@ -1,6 +1,197 @@
+use crate::utils::G;
 use crate::world::celestials::Celestials;
+use crate::world::collision::{CollisionPolicy, Outcome};
+use crate::world::cr3bp::Cr3bp;
 use crate::{Celestial, Vec3};
+use crate::world::atmosphere::Atmosphere;
+use crate::world::gravity::{Gravity, Octree};
+use crate::world::harmonics::Harmonics;
+use crate::world::rotation::{Ellipsoid, Rotation};
 use crate::world::spaceship::Spaceship;
+use crate::world::World;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::BTreeMap;
+
+pub fn scenario(name: &str) -> Option<World> {
+    match name {
+        "solar" => Some(solar()),
+        "belt" => Some(belt(2000)),
+        "mercury" => Some(mercury()),
+        "earth-moon" => Some(earth_moon()),
+        "sun-earth" => Some(sun_earth()),
+        "breakup" => Some(breakup()),
+        "rings" => Some(rings()),
+        _ => None,
+    }
+}
+
+/// Sun, Earth and Moon with two space stations in low Earth orbit. The
+/// simulation stops when a station hits the ground.
+pub fn solar() -> World {
+    let mut spaceships = BTreeMap::new();
+    for spaceship in [iss(), iss2()] {
+        spaceships.insert(spaceship.name(), spaceship);
+    }
+
+    let mut world = World::new(new_solar(), spaceships);
+    world.collision_policy = CollisionPolicy {
+        spaceships: Outcome::Stop,
+        celestials: Outcome::Merge,
+    };
+    world
+}
+
+/// The solar scenario plus `count` asteroids on near circular orbits
+/// between 2.2 and 3.2 AU, pulled with Barnes-Hut gravity. The belt is
+/// seeded, so every run starts from the same asteroids.
+pub fn belt(count: usize) -> World {
+    let mut world = solar();
+    let sun = sun();
+    let mut rng = StdRng::seed_from_u64(0);
+
+    for i in 0..count {
+        let radius = rng.gen_range(2.2..3.2) * 1.496e11;
+        let angle = rng.gen_range(0. ..std::f64::consts::TAU);
+        let inclination = rng.gen_range(-0.1..0.1_f64);
+        let speed = (G * sun.mass() / radius).sqrt();
+
+        let asteroid_pos = &sun.pos() + Vec3 {
+            x: radius * angle.cos(),
+            y: radius * angle.sin() * inclination.cos(),
+            z: radius * angle.sin() * inclination.sin(),
+        };
+        let asteroid_vel = &sun.vel() + Vec3 {
+            x: -speed * angle.sin(),
+            y: speed * angle.cos() * inclination.cos(),
+            z: speed * angle.cos() * inclination.sin(),
+        };
+
+        world.celestials.add(Celestial::new(
+            format!("Asteroid {}", i),
+            10_f64.powf(rng.gen_range(15. ..19.)),
+            asteroid_pos,
+            asteroid_vel,
+            10_000.,
+        ));
+    }
+
+    world.gravity = Gravity::BarnesHut { theta: Octree::DEFAULT_THETA };
+    // The energy sums over all pairs, which costs more than a tree step.
+    world.diagnostics_every = 1000;
+    world
+}
+
+/// The solar scenario right after ISS2 broke up into 20 000 fragments
+/// flying apart at up to 50 m/s. The fragments are seeded.
+pub fn breakup() -> World {
+    let mut world = solar();
+    let iss2 = world.spaceships.remove("ISS2").unwrap();
+    let mut rng = StdRng::seed_from_u64(0);
+    world.particles.debris(&iss2.pos(), &iss2.vel(), 50., 20_000, &mut rng);
+    world
+}
+
+/// The solar scenario with a ring of 10 000 particles over the Earth's
+/// equator between 3 and 5 Earth radii, and a swarm of 2000 particles
+/// scattered within 20 000 km of the Moon. Both are seeded.
+pub fn rings() -> World {
+    let mut world = solar();
+    let earth = earth();
+    let moon = moon();
+    let mut rng = StdRng::seed_from_u64(0);
+    let (inner, outer) = (3. * earth.rad(), 5. * earth.rad());
+    world.particles.ring(&earth, inner, outer, 10_000, &mut rng);
+    world.particles.swarm(&moon.pos(), &moon.vel(), 2e7, 100., 2000, &mut rng);
+    world
+}
+
+/// The Sun and Mercury alone with the post-Newtonian correction, which
+/// turns Mercury's perihelion by 43 arcseconds per century.
+pub fn mercury() -> World {
+    let mut world = World::new(new_mercury(), BTreeMap::new());
+    world.relativity = true;
+    world
+}
+
+/// The Earth and the Moon 384 400 km apart, see `restricted`.
+pub fn earth_moon() -> World {
+    restricted(earth(), moon(), 3.844e8)
+}
+
+/// The Sun and the Earth 1 AU apart, see `restricted`.
+pub fn sun_earth() -> World {
+    restricted(sun(), earth(), 1.496e11)
+}
+
+/// Two primaries `distance` apart as a circular restricted three-body
+/// problem, at rest in the rotating frame, with probes released at rest
+/// near L1 and at L4. Harmonics, drag and radiation pressure play no part
+/// in this mode.
+fn restricted(
+    mut primary: Celestial,
+    mut secondary: Celestial,
+    distance: f64,
+) -> World {
+    let cr3bp = Cr3bp::new(&primary, &secondary, distance);
+    let (primary_pos, secondary_pos) = cr3bp.primaries();
+    primary.set_state(primary_pos, Vec3::default());
+    secondary.set_state(secondary_pos, Vec3::default());
+
+    let [l1, _, _, l4, _] = cr3bp.lagrange_points();
+    // A thousandth of the way to the primary, so the probe drifts off L1.
+    let l1 = &l1 + (&cr3bp.primaries().0 - &l1) * 1e-3;
+    let mut spaceships = BTreeMap::new();
+    for (name, pos) in [("L1 probe", l1), ("L4 probe", l4)] {
+        spaceships.insert(
+            name.to_string(),
+            Spaceship::new(name.to_string(), 1000., pos, Vec3::default()),
+        );
+    }
+
+    let mut celestials = Celestials::new();
+    celestials.add(primary);
+    celestials.add(secondary);
+    let mut world = World::new(celestials, spaceships);
+    world.cr3bp = Some(cr3bp);
+    world
+}
+
+/// Sun and Mercury at perihelion, with the Sun moving so that the
+/// barycenter stays at rest.
+pub fn new_mercury() -> Celestials {
+    let mut sun = sun();
+
+    let mercury_name = "Mercury".to_string();
+    let mercury_mass = 3.3011_f64 * 10_f64.powi(23);
+    let semi_major_axis = 5.7909050_f64 * 10_f64.powi(10);
+    let eccentricity = 0.205630;
+    let perihelion = semi_major_axis * (1. - eccentricity);
+    let mu = G * (sun.mass() + mercury_mass);
+    let speed = (mu * (1. + eccentricity) / perihelion).sqrt();
+    let mercury_pos = &sun.pos() + Vec3 { x: perihelion, y: 0., z: 0. };
+    let mercury_vel = &sun.vel() + Vec3 { x: 0., y: speed, z: 0. };
+    let mercury_rad = 2.4397_f64 * 10_f64.powi(6);
+
+    let mercury = Celestial::new(
+        mercury_name,
+        mercury_mass,
+        mercury_pos,
+        mercury_vel,
+        mercury_rad,
+    );
+
+    let share = mercury_mass / sun.mass();
+    sun.set_state(
+        &sun.pos() + &mercury.pos() * -share,
+        &sun.vel() + &mercury.vel() * -share,
+    );
+
+    let mut celestials = Celestials::new();
+    celestials.add(sun);
+    celestials.add(mercury);
+    celestials
+}
 
 pub fn sun() -> Celestial {
     let sun_name = "Sun".to_string();
@@ -8,6 +199,7 @@ pub fn sun() -> Celestial {
     let sun_pos = Vec3::default();
     let sun_vel = Vec3::default();
     let sun_rad = 6.9634_f64 * 10_f64.powi(8);
+    let sun_luminosity = 3.828_f64 * 10_f64.powi(26);
 
     Celestial::new(
         sun_name,
@@ -16,6 +208,7 @@ pub fn sun() -> Celestial {
         sun_vel,
         sun_rad,
     )
+    .with_luminosity(sun_luminosity)
 }
 
 pub fn earth() -> Celestial {
@@ -26,6 +219,29 @@ pub fn earth() -> Celestial {
     let earth_pos = &sun.pos() + Vec3 { x: 1.521_f64 * 10_f64.powi(11), y: 0., z: 0., };
     let earth_vel = &sun.vel() + Vec3 { x: 0., y: 29290., z: 0., };
     let earth_rad = 6.371_f64 * 10_f64.powi(6);
+    let obliquity = 23.44_f64.to_radians();
+    let earth_pole = Vec3 { x: 0., y: -obliquity.sin(), z: obliquity.cos() };
+    let earth_harmonics = Harmonics {
+        radius: 6.378137_f64 * 10_f64.powi(6),
+        pole: earth_pole.clone(),
+        zonal: vec![1.08263e-3, -2.53266e-6, -1.61962e-6],
+    };
+    // IAU rotation elements, with TT standing in for UT1. The IAU's W of
+    // 190.147° counts from the node at right ascension 90°, a quarter turn
+    // east of the equinox that the meridian is measured from here.
+    let earth_rotation = Rotation {
+        pole: earth_pole,
+        period: 86_164.098_9,
+        meridian: 280.147_f64.to_radians(),
+    };
+    let earth_ellipsoid = Ellipsoid {
+        radius: 6_378_137.,
+        flattening: 1. / 298.257_223_563,
+    };
+    let earth_atmosphere = Atmosphere {
+        density: earth_density(),
+        rotation: earth_rotation.angular_velocity(),
+    };
 
     Celestial::new(
         earth_name,
@@ -34,6 +250,48 @@ pub fn earth() -> Celestial {
         earth_vel,
         earth_rad,
     )
+    .with_harmonics(earth_harmonics)
+    .with_atmosphere(earth_atmosphere)
+    .with_rotation(earth_rotation)
+    .with_ellipsoid(earth_ellipsoid)
+}
+
+/// Density over altitude of the exponential model in Vallado's
+/// "Fundamentals of Astrodynamics and Applications", for moderate solar
+/// activity.
+fn earth_density() -> Vec<(f64, f64)> {
+    [
+        (0., 1.225),
+        (25., 3.899e-2),
+        (30., 1.774e-2),
+        (40., 3.972e-3),
+        (50., 1.057e-3),
+        (60., 3.206e-4),
+        (70., 8.770e-5),
+        (80., 1.905e-5),
+        (90., 3.396e-6),
+        (100., 5.297e-7),
+        (110., 9.661e-8),
+        (120., 2.438e-8),
+        (130., 8.484e-9),
+        (140., 3.845e-9),
+        (150., 2.070e-9),
+        (180., 5.464e-10),
+        (200., 2.789e-10),
+        (250., 7.248e-11),
+        (300., 2.418e-11),
+        (350., 9.518e-12),
+        (400., 3.725e-12),
+        (450., 1.585e-12),
+        (500., 6.967e-13),
+        (600., 1.454e-13),
+        (700., 3.614e-14),
+        (800., 1.170e-14),
+        (900., 5.245e-15),
+        (1000., 3.019e-15),
+    ]
+    .map(|(km, density)| (km * 1000., density))
+    .to_vec()
 }
 
 pub fn moon() -> Celestial {
@@ -82,6 +340,9 @@ pub fn iss() -> Spaceship {
         iss_pos,
         iss_vel,
     )
+    .with_drag(1100., 2.2)
+    .with_radiation_pressure(2500., 0.3)
+    .with_inertia(1.3e8, 1.1e8, 2.0e8)
 }
 
 pub fn iss2() -> Spaceship {
@@ -98,4 +359,53 @@ pub fn iss2() -> Spaceship {
         iss_pos,
         iss_vel,
     )
+    .with_drag(1100., 2.2)
+    .with_radiation_pressure(2500., 0.3)
+    .with_inertia(1.3e8, 1.1e8, 2.0e8)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulation::integrator::{Ias15, Integrator};
+
+    /// Direction of Mercury's perihelion from the Laplace-Runge-Lenz vector
+    /// relative to the Sun, as an angle in the orbital plane.
+    fn perihelion(world: &World) -> f64 {
+        let bodies = world.celestials.get();
+        let (sun, mercury) = (&bodies["Sun"], &bodies["Mercury"]);
+        let r = mercury.pos() - &sun.pos();
+        let v = mercury.vel() - &sun.vel();
+        let mu = G * (sun.mass() + mercury.mass());
+
+        let h = r.x * v.y - r.y * v.x;
+        let unit = r.normalize().unit_direction;
+        (-v.x * h / mu - unit.y).atan2(v.y * h / mu - unit.x)
+    }
+
+    #[test]
+    fn test_mercury_perihelion_precession() {
+        let orbits = 40.;
+        let period = 87.9691 * 86_400.;
+        let mut shifts = Vec::new();
+        for relativity in [false, true] {
+            let mut world = mercury();
+            world.relativity = relativity;
+            let start = perihelion(&world);
+
+            let mut ias15 = Ias15::new(Ias15::DEFAULT_EPSILON);
+            let mut remaining = orbits * period;
+            while remaining > 0. {
+                remaining -= ias15.step(&mut world, remaining);
+            }
+            shifts.push(perihelion(&world) - start);
+        }
+
+        let arcseconds = (shifts[1] - shifts[0]).to_degrees() * 3600.;
+        let per_century = arcseconds * 36_525. / (orbits * 87.9691);
+        assert!(
+            (per_century - 42.98).abs() < 1.,
+            "{per_century} arcsec per century"
+        );
+    }
 }
@@ -0,0 +1,175 @@
+use super::celestials::Celestial;
+use crate::utils::{Vec3, G};
+use serde::{Deserialize, Serialize};
+
+/// Circular restricted three-body problem of two primaries on circular
+/// orbits around their barycenter. States are given in the frame that
+/// rotates with them around the z axis, with the barycenter at the origin
+/// and the secondary on the positive x axis, in m and m/s.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Cr3bp {
+    pub primary: String,
+    pub secondary: String,
+    /// Gravitational parameters of the primaries, `G m`.
+    pub mu: (f64, f64),
+    /// Distance between the primaries.
+    pub distance: f64,
+    /// Angular velocity of the frame.
+    pub mean_motion: f64,
+}
+
+impl Cr3bp {
+    pub fn new(
+        primary: &Celestial,
+        secondary: &Celestial,
+        distance: f64,
+    ) -> Self {
+        let mu = (G * primary.mass(), G * secondary.mass());
+        Self {
+            primary: primary.name(),
+            secondary: secondary.name(),
+            mu,
+            distance,
+            mean_motion: ((mu.0 + mu.1) / distance.powi(3)).sqrt(),
+        }
+    }
+
+    /// `m2 / (m1 + m2)`.
+    pub fn mass_ratio(&self) -> f64 {
+        self.mu.1 / (self.mu.0 + self.mu.1)
+    }
+
+    /// Fixed positions of the primary and the secondary.
+    pub fn primaries(&self) -> (Vec3, Vec3) {
+        let ratio = self.mass_ratio();
+        let on_axis = |x: f64| Vec3 { x, y: 0., z: 0. };
+        (
+            on_axis(-ratio * self.distance),
+            on_axis((1. - ratio) * self.distance),
+        )
+    }
+
+    /// Gravity of the primaries plus the Coriolis and centrifugal terms of
+    /// the rotating frame.
+    pub fn acceleration(&self, pos: &Vec3, vel: &Vec3) -> Vec3 {
+        let (primary, secondary) = self.primaries();
+        let n = self.mean_motion;
+
+        let mut acceleration = Vec3 {
+            x: n * n * pos.x + 2. * n * vel.y,
+            y: n * n * pos.y - 2. * n * vel.x,
+            z: 0.,
+        };
+        for (center, mu) in [(primary, self.mu.0), (secondary, self.mu.1)] {
+            let offset = pos - &center;
+            let distance_sq = offset.normalize().distance_sq;
+            if distance_sq > 1. {
+                acceleration +=
+                    offset * (-mu / (distance_sq * distance_sq.sqrt()));
+            }
+        }
+        acceleration
+    }
+
+    /// The Jacobi constant `n² (x² + y²) + 2 μ1 / r1 + 2 μ2 / r2 - v²`,
+    /// which the motion in the rotating frame conserves.
+    pub fn jacobi(&self, pos: &Vec3, vel: &Vec3) -> f64 {
+        let (primary, secondary) = self.primaries();
+        let n = self.mean_motion;
+        let r1 = (pos - &primary).normalize().distance;
+        let r2 = (pos - &secondary).normalize().distance;
+        n * n * (pos.x * pos.x + pos.y * pos.y)
+            + 2. * self.mu.0 / r1
+            + 2. * self.mu.1 / r2
+            - vel * vel
+    }
+
+    /// L1 to L5. L1 lies between the primaries, L2 beyond the secondary, L3
+    /// beyond the primary and L4 leads the secondary by 60°.
+    pub fn lagrange_points(&self) -> [Vec3; 5] {
+        let (primary, secondary) = self.primaries();
+        let (x1, x2) = (primary.x, secondary.x);
+        let hill = self.distance * (self.mass_ratio() / 3.).cbrt();
+        let on_axis = |x: f64| Vec3 { x, y: 0., z: 0. };
+        let triangle = |y: f64| Vec3 {
+            x: (x1 + x2) / 2.,
+            y,
+            z: 0.,
+        };
+        let height = self.distance * 3_f64.sqrt() / 2.;
+
+        [
+            on_axis(self.collinear(x2 - hill)),
+            on_axis(self.collinear(x2 + hill)),
+            on_axis(self.collinear(-x2)),
+            triangle(height),
+            triangle(-height),
+        ]
+    }
+
+    /// Newton's method on the x axis, where the pulls of the primaries and
+    /// the centrifugal term cancel.
+    fn collinear(&self, mut x: f64) -> f64 {
+        let (primary, secondary) = self.primaries();
+        let n2 = self.mean_motion.powi(2);
+        for _ in 0..50 {
+            let mut f = n2 * x;
+            let mut df = n2;
+            for (center, mu) in
+                [(primary.x, self.mu.0), (secondary.x, self.mu.1)]
+            {
+                let r = (x - center).abs();
+                f -= mu * (x - center) / r.powi(3);
+                df += 2. * mu / r.powi(3);
+            }
+            let step = f / df;
+            x -= step;
+            if step.abs() < 1e-12 * self.distance {
+                break;
+            }
+        }
+        x
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulation::integrator::{DormandPrince, Integrator};
+    use crate::world::{config, World};
+
+    #[test]
+    fn test_lagrange_points_are_equilibria() {
+        let world = config::earth_moon();
+        let cr3bp = world.cr3bp.unwrap();
+        let scale = cr3bp.mu.0 / cr3bp.distance.powi(2);
+        for point in cr3bp.lagrange_points() {
+            let a = cr3bp.acceleration(&point, &Vec3::default());
+            assert!(a.normalize().distance < 1e-9 * scale, "{:?}", point);
+        }
+
+        // L1 of the Earth-Moon system is about 326 000 km from the Earth.
+        let (earth, _) = cr3bp.primaries();
+        let l1 = (&cr3bp.lagrange_points()[0] - &earth).normalize().distance;
+        assert!((l1 - 3.26e8).abs() < 1e6, "{l1}");
+    }
+
+    #[test]
+    fn test_jacobi_constant_is_conserved() {
+        let mut world = config::earth_moon();
+        let cr3bp = world.cr3bp.clone().unwrap();
+        let jacobi = |world: &World| {
+            let ship = &world.spaceships["L1 probe"];
+            cr3bp.jacobi(&ship.pos(), &ship.vel())
+        };
+        let start = jacobi(&world);
+
+        let mut dopri = DormandPrince::new(1e-12);
+        let mut remaining = 10. * 86_400.;
+        while remaining > 0. {
+            remaining -= dopri.step(&mut world, remaining);
+        }
+        let drift = ((jacobi(&world) - start) / start).abs();
+        assert!(drift < 1e-9, "{drift}");
+    }
+}
@@ -0,0 +1,119 @@
+use super::World;
+use crate::utils::{Vec3, G};
+use serde::{Deserialize, Serialize};
+
+/// Quantities that Newtonian gravity between celestials conserves. The
+/// spaceships are left out, since they feel the celestials but do not pull
+/// back.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Diagnostics {
+    pub time: f64,
+    pub energy: f64,
+    pub momentum: Vec3,
+    /// Around the origin.
+    pub angular_momentum: Vec3,
+    pub barycenter: Vec3,
+    pub mass: f64,
+    /// `Σ m |v|`, the scale momentum errors are measured against.
+    momentum_scale: f64,
+}
+
+/// Changes of [`Diagnostics`] since the start, as relative errors except
+/// for the barycenter.
+#[derive(Clone, Debug)]
+pub struct Drift {
+    pub energy: f64,
+    pub momentum: f64,
+    pub angular_momentum: f64,
+    /// Distance in m between the barycenter and where its initial momentum
+    /// should have carried it.
+    pub barycenter: f64,
+}
+
+impl Diagnostics {
+    pub fn new(world: &World) -> Self {
+        let celestials: Vec<_> = world.celestials.values().collect();
+
+        let mut diagnostics = Self {
+            time: world.time,
+            energy: 0.,
+            momentum: Vec3::default(),
+            angular_momentum: Vec3::default(),
+            barycenter: Vec3::default(),
+            mass: 0.,
+            momentum_scale: 0.,
+        };
+        for (i, a) in celestials.iter().enumerate() {
+            let (pos, vel, mass) = (a.pos(), a.vel(), a.mass());
+            diagnostics.energy += mass * (&vel * &vel) / 2.;
+            for b in &celestials[i + 1..] {
+                let distance = (b.pos() - &pos).normalize().distance;
+                if distance > 1. {
+                    diagnostics.energy -= G * mass * b.mass() / distance;
+                }
+            }
+            diagnostics.angular_momentum += pos.cross(&vel) * mass;
+            diagnostics.barycenter += &pos * mass;
+            diagnostics.momentum_scale += vel.normalize().distance * mass;
+            diagnostics.momentum += vel * mass;
+            diagnostics.mass += mass;
+        }
+        if diagnostics.mass > 0. {
+            diagnostics.barycenter *= 1. / diagnostics.mass;
+        }
+        diagnostics
+    }
+
+    pub fn drift(&self, start: &Diagnostics) -> Drift {
+        let relative = |value: f64, scale: f64| {
+            if scale > 0. {
+                value / scale
+            } else {
+                value
+            }
+        };
+        let norm = |v: Vec3| v.normalize().distance;
+
+        let mut expected = start.barycenter.clone();
+        if start.mass > 0. {
+            expected +=
+                &start.momentum * ((self.time - start.time) / start.mass);
+        }
+        Drift {
+            energy: relative(self.energy - start.energy, start.energy.abs()),
+            momentum: relative(
+                norm(self.momentum.clone() - &start.momentum),
+                start.momentum_scale,
+            ),
+            angular_momentum: relative(
+                norm(self.angular_momentum.clone() - &start.angular_momentum),
+                norm(start.angular_momentum.clone()),
+            ),
+            barycenter: norm(self.barycenter.clone() - &expected),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulation::integrator::{Integrator, Yoshida4};
+    use crate::world::config;
+
+    #[test]
+    fn test_drift_of_solar_day() {
+        let mut world = World::new(config::new_solar(), Default::default());
+        world.update_diagnostics();
+        for _ in 0..24 {
+            Yoshida4.step(&mut world, 3600.);
+            world.time += 3600.;
+        }
+        world.update_diagnostics();
+
+        let drift = world.conservation_drift().unwrap();
+        assert!(drift.energy.abs() < 1e-10, "{:?}", drift);
+        assert!(drift.momentum < 1e-14, "{:?}", drift);
+        assert!(drift.angular_momentum < 1e-14, "{:?}", drift);
+        assert!(drift.barycenter < 1e-3, "{:?}", drift);
+    }
+}
@@ -0,0 +1,246 @@
+use crate::utils::{NormVec3, Vec3, G};
+use serde::{Deserialize, Serialize};
+
+/// How the pull of all celestials on a point is summed up.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Gravity {
+    /// Exact sum over every celestial, O(N) per point.
+    Direct,
+    /// Barnes-Hut octree, O(log N) per point. Cells that look smaller than
+    /// `theta` radians from the point are replaced by their center of mass.
+    BarnesHut { theta: f64 },
+}
+
+impl Gravity {
+    /// Parses `direct` or `barnes-hut[:theta]`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.split_once(':') {
+            None if name == "direct" => Some(Gravity::Direct),
+            None if name == "barnes-hut" => Some(Gravity::BarnesHut {
+                theta: Octree::DEFAULT_THETA,
+            }),
+            Some(("barnes-hut", theta)) => Some(Gravity::BarnesHut {
+                theta: theta.parse().ok()?,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// Cells deeper than this keep all their bodies in one leaf, so coincident
+/// bodies can't split cells forever.
+const MAX_DEPTH: u32 = 32;
+
+pub struct Octree {
+    nodes: Vec<Node>,
+}
+
+struct Node {
+    center: Vec3,
+    half_size: f64,
+    mass: f64,
+    center_of_mass: Vec3,
+    children: Vec<usize>,
+    bodies: Vec<(Vec3, f64)>,
+}
+
+impl Node {
+    fn contains(&self, point: &Vec3) -> bool {
+        (point.x - self.center.x).abs() <= self.half_size
+            && (point.y - self.center.y).abs() <= self.half_size
+            && (point.z - self.center.z).abs() <= self.half_size
+    }
+}
+
+impl Octree {
+    pub const DEFAULT_THETA: f64 = 0.5;
+
+    /// Builds the tree over `(position, mass)` pairs.
+    pub fn new(bodies: &[(Vec3, f64)]) -> Self {
+        let mut tree = Self { nodes: Vec::new() };
+        let Some((first, _)) = bodies.first() else {
+            return tree;
+        };
+
+        let (mut min, mut max) = (first.clone(), first.clone());
+        for (pos, _) in bodies {
+            min.x = min.x.min(pos.x);
+            min.y = min.y.min(pos.y);
+            min.z = min.z.min(pos.z);
+            max.x = max.x.max(pos.x);
+            max.y = max.y.max(pos.y);
+            max.z = max.z.max(pos.z);
+        }
+        let center = (min.clone() + &max) / 2.;
+        let half_size =
+            (max.x - min.x).max(max.y - min.y).max(max.z - min.z) / 2.;
+
+        tree.build(bodies, (0..bodies.len()).collect(), center, half_size, 0);
+        tree
+    }
+
+    fn build(
+        &mut self,
+        bodies: &[(Vec3, f64)],
+        indices: Vec<usize>,
+        center: Vec3,
+        half_size: f64,
+        depth: u32,
+    ) -> usize {
+        let mut mass = 0.;
+        let mut center_of_mass = Vec3::default();
+        for &i in &indices {
+            mass += bodies[i].1;
+            center_of_mass += &bodies[i].0 * bodies[i].1;
+        }
+        if mass > 0. {
+            center_of_mass *= 1. / mass;
+        }
+
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            center: center.clone(),
+            half_size,
+            mass,
+            center_of_mass,
+            children: Vec::new(),
+            bodies: Vec::new(),
+        });
+
+        if indices.len() <= 1 || depth == MAX_DEPTH {
+            self.nodes[id].bodies =
+                indices.iter().map(|&i| bodies[i].clone()).collect();
+            return id;
+        }
+
+        let mut octants: [Vec<usize>; 8] = Default::default();
+        for i in indices {
+            let pos = &bodies[i].0;
+            let octant = (pos.x > center.x) as usize
+                | ((pos.y > center.y) as usize) << 1
+                | ((pos.z > center.z) as usize) << 2;
+            octants[octant].push(i);
+        }
+
+        let quarter = half_size / 2.;
+        for (octant, indices) in octants.into_iter().enumerate() {
+            if indices.is_empty() {
+                continue;
+            }
+            let sign = |bit: usize| if octant & bit > 0 { 1. } else { -1. };
+            let child_center = &center
+                + Vec3 {
+                    x: sign(1) * quarter,
+                    y: sign(2) * quarter,
+                    z: sign(4) * quarter,
+                };
+            let child =
+                self.build(bodies, indices, child_center, quarter, depth + 1);
+            self.nodes[id].children.push(child);
+        }
+        id
+    }
+
+    pub fn acceleration(&self, origin: &Vec3, theta: f64) -> Vec3 {
+        let mut acceleration = Vec3::default();
+        if self.nodes.is_empty() {
+            return acceleration;
+        }
+
+        let mut stack = vec![0];
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id];
+
+            if node.children.is_empty() {
+                for (pos, mass) in &node.bodies {
+                    let NormVec3 {
+                        distance_sq,
+                        unit_direction,
+                        ..
+                    } = (pos - origin).normalize();
+                    if distance_sq > 1. {
+                        acceleration +=
+                            unit_direction * (G * mass / distance_sq);
+                    }
+                }
+                continue;
+            }
+
+            let NormVec3 {
+                distance_sq,
+                distance,
+                unit_direction,
+            } = (&node.center_of_mass - origin).normalize();
+            if !node.contains(origin) && 2. * node.half_size < theta * distance
+            {
+                acceleration += unit_direction * (G * node.mass / distance_sq);
+            } else {
+                stack.extend(&node.children);
+            }
+        }
+
+        acceleration
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::celestials::{Celestial, Celestials};
+    use crate::world::World;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    fn cluster(count: usize) -> World {
+        let mut rng = StdRng::seed_from_u64(1);
+        let mut celestials = Celestials::new();
+        for i in 0..count {
+            let mut coordinate = || rng.gen_range(-1e12..1e12);
+            let pos = Vec3 {
+                x: coordinate(),
+                y: coordinate(),
+                z: coordinate(),
+            };
+            let mass = rng.gen_range(1e28..1e30);
+            celestials.add(Celestial::new(
+                format!("Star {}", i),
+                mass,
+                pos,
+                Vec3::default(),
+                1.,
+            ));
+        }
+        World::new(celestials, Default::default())
+    }
+
+    fn relative_errors(world: &mut World, theta: f64) -> Vec<f64> {
+        world.gravity = Gravity::Direct;
+        let direct = world.accelerations();
+        world.gravity = Gravity::BarnesHut { theta };
+        let tree = world.accelerations();
+
+        direct
+            .iter()
+            .zip(&tree)
+            .map(|(direct, tree)| {
+                (tree - direct).normalize().distance
+                    / direct.normalize().distance
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_barnes_hut_accuracy() {
+        let mut world = cluster(3000);
+
+        let errors = relative_errors(&mut world, 0.5);
+        let mean = errors.iter().sum::<f64>() / errors.len() as f64;
+        let max = errors.iter().cloned().fold(0., f64::max);
+        assert!(mean < 5e-3, "mean relative error {mean}");
+        assert!(max < 1e-1, "max relative error {max}");
+
+        let errors = relative_errors(&mut world, 0.);
+        let max = errors.iter().cloned().fold(0., f64::max);
+        assert!(max < 1e-9, "max relative error {max}");
+    }
+}
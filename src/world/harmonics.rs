@@ -0,0 +1,200 @@
+use crate::utils::{NormVec3, Vec3, G};
+use serde::{Deserialize, Serialize};
+
+/// Zonal spherical harmonics of a celestial's gravity field, the part that
+/// is symmetric around the pole and so does not depend on its rotation.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Harmonics {
+    /// Reference radius of the coefficients, usually the equatorial one.
+    pub radius: f64,
+    /// Unit vector along the north pole.
+    pub pole: Vec3,
+    /// `J2, J3, J4, ...`, the negated normalized zonal coefficients.
+    pub zonal: Vec<f64>,
+}
+
+impl Harmonics {
+    /// Acceleration beyond the point mass pull at `offset` from the center
+    /// of a celestial of `mass`.
+    pub fn acceleration(&self, mass: f64, offset: &Vec3) -> Vec3 {
+        let NormVec3 {
+            distance,
+            distance_sq,
+            unit_direction,
+        } = offset.normalize();
+        if distance_sq <= 1. {
+            return Vec3::default();
+        }
+        let sin_latitude = &unit_direction * &self.pole;
+
+        // Legendre polynomials and their derivatives by recursion.
+        let (mut p, mut p_prev) = (sin_latitude, 1.);
+        let (mut dp, mut dp_prev) = (1., 0.);
+        let mut ratio = self.radius / distance;
+        let mut radial = 0.;
+        let mut polar = 0.;
+        for (k, j) in self.zonal.iter().enumerate() {
+            let n = (k + 1) as f64;
+            let p_next =
+                ((2. * n + 1.) * sin_latitude * p - n * p_prev) / (n + 1.);
+            let dp_next = dp_prev + (2. * n + 1.) * p;
+            (p_prev, p, dp_prev, dp) = (p, p_next, dp, dp_next);
+            ratio *= self.radius / distance;
+
+            // a_n = μ J_n R^n / r^(n+2) ((n+1) P_n r̂ - P_n' (k̂ - s r̂))
+            radial += j * ratio * ((n + 2.) * p + dp * sin_latitude);
+            polar -= j * ratio * dp;
+        }
+
+        let scale = G * mass / distance_sq;
+        unit_direction * (scale * radial) + &(&self.pole * (scale * polar))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::simulation::{integrator, Integrator};
+    use crate::world::celestials::{Celestial, Celestials};
+    use crate::world::spaceship::Spaceship;
+    use crate::world::World;
+    use std::collections::BTreeMap;
+
+    const J2: f64 = 1.082_63e-3;
+    const RADIUS: f64 = 6_378_137.;
+
+    #[test]
+    fn test_iss_nodal_regression() {
+        // A tilted pole, so the frame does not line up with the world axes.
+        let obliquity = 23.44_f64.to_radians();
+        let pole = Vec3 {
+            x: 0.,
+            y: -obliquity.sin(),
+            z: obliquity.cos(),
+        };
+        let harmonics = Harmonics {
+            radius: RADIUS,
+            pole: pole.clone(),
+            zonal: vec![J2],
+        };
+        let mass = 5.972e24;
+        let earth = Celestial::new(
+            "Earth".to_string(),
+            mass,
+            Vec3::default(),
+            Vec3::default(),
+            6.371e6,
+        )
+        .with_harmonics(harmonics);
+        let mut celestials = Celestials::new();
+        celestials.add(earth);
+
+        // Circular orbit at 51.6° to the equator, starting at the node.
+        let a = RADIUS + 420_000.;
+        let inclination = 51.6_f64.to_radians();
+        let east = Vec3 {
+            x: 1.,
+            y: 0.,
+            z: 0.,
+        };
+        let north = Vec3 {
+            x: 0.,
+            y: obliquity.cos(),
+            z: obliquity.sin(),
+        };
+        let speed = (G * mass / a).sqrt();
+        let pos = &east * a;
+        let vel = north * (speed * inclination.cos())
+            + &(&pole * (speed * inclination.sin()));
+
+        let mut spaceships = BTreeMap::new();
+        spaceships.insert(
+            "ISS".to_string(),
+            Spaceship::new("ISS".to_string(), 4e5, pos, vel),
+        );
+        let mut world = World::new(celestials, spaceships);
+
+        let node = |world: &World| {
+            let ship = &world.spaceships["ISS"];
+            let h = ship.pos().cross(&ship.vel());
+            pole.cross(&h)
+        };
+        let node_0 = node(&world);
+
+        let mean_motion = (G * mass / a.powi(3)).sqrt();
+        let period = std::f64::consts::TAU / mean_motion;
+        let orbits = 30.;
+        let delta_t = period / 1000.;
+        let mut yoshida = integrator::Yoshida4;
+        for _ in 0..(orbits * 1000.) as usize {
+            yoshida.step(&mut world, delta_t);
+        }
+
+        let node_1 = node(&world);
+        let drift = (&node_0.cross(&node_1) * &pole).atan2(&node_0 * &node_1);
+        let expected = -1.5
+            * mean_motion
+            * J2
+            * (RADIUS / a).powi(2)
+            * inclination.cos()
+            * orbits
+            * period;
+
+        let error = ((drift - expected) / expected).abs();
+        assert!(error < 1e-2, "RAAN drift {drift}, expected {expected}");
+    }
+
+    /// Zonal part of the potential, `μ/r Σ J_n (R/r)^n P_n(sin φ)`.
+    fn potential(harmonics: &Harmonics, mass: f64, offset: &Vec3) -> f64 {
+        let r = offset.normalize().distance;
+        let s = &offset.normalize().unit_direction * &harmonics.pole;
+        let legendre = [
+            (3. * s * s - 1.) / 2.,
+            (5. * s.powi(3) - 3. * s) / 2.,
+            (35. * s.powi(4) - 30. * s * s + 3.) / 8.,
+        ];
+        let mut potential = 0.;
+        for (n, (j, p)) in harmonics.zonal.iter().zip(legendre).enumerate() {
+            potential += j * (harmonics.radius / r).powi(n as i32 + 2) * p;
+        }
+        G * mass / r * potential
+    }
+
+    #[test]
+    fn test_gradient_of_potential() {
+        let harmonics = Harmonics {
+            radius: RADIUS,
+            pole: Vec3 {
+                x: 0.6,
+                y: 0.,
+                z: 0.8,
+            },
+            zonal: vec![J2, -2.53266e-6, -1.61962e-6],
+        };
+        let mass = 5.972e24;
+        let offset = Vec3 {
+            x: 5e6,
+            y: -3e6,
+            z: 2e6,
+        };
+
+        let h = 1.;
+        let derivative = |step: Vec3| {
+            let forward = offset.clone() + &step;
+            let backward = offset.clone() + &(step * -1.);
+            (potential(&harmonics, mass, &forward)
+                - potential(&harmonics, mass, &backward))
+                / (2. * h)
+        };
+        let gradient = Vec3 {
+            x: derivative(Vec3 { x: h, y: 0., z: 0. }),
+            y: derivative(Vec3 { x: 0., y: h, z: 0. }),
+            z: derivative(Vec3 { x: 0., y: 0., z: h }),
+        };
+
+        let acceleration = harmonics.acceleration(mass, &offset);
+        // The acceleration is minus the gradient of the potential.
+        let error = (acceleration - &(gradient * -1.)).normalize().distance;
+        assert!(error < 1e-9, "error {error}");
+    }
+}
@@ -0,0 +1,161 @@
+use crate::utils::Vec3;
+use std::f64::consts::TAU;
+
+/// Moves a body at `pos` with `vel` relative to a point mass with
+/// gravitational parameter `mu` along its conic for `delta_t`, with the
+/// universal variable formulation so that ellipses, parabolas and
+/// hyperbolas are handled alike.
+pub fn propagate(
+    mu: f64,
+    pos: &Vec3,
+    vel: &Vec3,
+    delta_t: f64,
+) -> (Vec3, Vec3) {
+    let r0 = pos.normalize().distance;
+    let radial = (pos * vel) / r0;
+    // Inverse of the semi-major axis, negative for hyperbolas.
+    let alpha = 2. / r0 - (vel * vel) / mu;
+
+    if delta_t == 0. {
+        return (pos.clone(), vel.clone());
+    }
+
+    // Whole revolutions of an ellipse change nothing.
+    let mut delta_t = delta_t;
+    if alpha > 0. {
+        let period = TAU / (mu * alpha.powi(3)).sqrt();
+        delta_t %= period;
+    }
+
+    let sqrt_mu = mu.sqrt();
+    // Starting guesses from Vallado, Fundamentals of Astrodynamics.
+    let mut chi = if alpha > 1e-12 / r0 {
+        sqrt_mu * alpha * delta_t
+    } else if alpha < -1e-12 / r0 {
+        let a = 1. / alpha;
+        let sign = delta_t.signum();
+        sign * (-a).sqrt()
+            * ((-2. * mu * alpha * delta_t)
+                / (pos * vel + sign * (-mu * a).sqrt() * (1. - r0 * alpha)))
+                .ln()
+    } else {
+        sqrt_mu * delta_t / r0
+    };
+    if !chi.is_finite() {
+        chi = sqrt_mu * delta_t / r0;
+    }
+    for _ in 0..100 {
+        let z = alpha * chi * chi;
+        let (c, s) = stumpff(z);
+        let f = r0 * radial / sqrt_mu * chi * chi * c
+            + (1. - alpha * r0) * chi.powi(3) * s
+            + r0 * chi
+            - sqrt_mu * delta_t;
+        let df = r0 * radial / sqrt_mu * chi * (1. - z * s)
+            + (1. - alpha * r0) * chi * chi * c
+            + r0;
+        let step = f / df;
+        chi -= step;
+        if step.abs() <= 1e-12 * chi.abs().max(1e-12) {
+            break;
+        }
+    }
+
+    let z = alpha * chi * chi;
+    let (c, s) = stumpff(z);
+    let f = 1. - chi * chi / r0 * c;
+    let g = delta_t - chi.powi(3) / sqrt_mu * s;
+    let new_pos = pos * f + &(vel * g);
+    let r = new_pos.normalize().distance;
+    let df = sqrt_mu / (r * r0) * (z * s - 1.) * chi;
+    let dg = 1. - chi * chi / r * c;
+    let new_vel = pos * df + &(vel * dg);
+    (new_pos, new_vel)
+}
+
+/// Closest distance to the point mass on the conic through `pos` and `vel`,
+/// whether it lies ahead or behind.
+pub fn periapsis(mu: f64, pos: &Vec3, vel: &Vec3) -> f64 {
+    let r = pos.normalize().distance;
+    let eccentricity =
+        (pos * ((vel * vel) - mu / r) - &(vel * (pos * vel))) * (1. / mu);
+    let h = pos.cross(vel);
+    (&h * &h) / (mu * (1. + eccentricity.normalize().distance))
+}
+
+/// Stumpff functions `C(z)` and `S(z)`.
+fn stumpff(z: f64) -> (f64, f64) {
+    if z.abs() < 1e-6 {
+        (1. / 2. - z / 24., 1. / 6. - z / 120.)
+    } else if z > 0. {
+        let root = z.sqrt();
+        ((1. - root.cos()) / z, (root - root.sin()) / root.powi(3))
+    } else {
+        let root = (-z).sqrt();
+        ((root.cosh() - 1.) / -z, (root.sinh() - root) / root.powi(3))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::utils::G;
+
+    const MU: f64 = G * 5.972e24;
+
+    fn energy(pos: &Vec3, vel: &Vec3) -> f64 {
+        (vel * vel) / 2. - MU / pos.normalize().distance
+    }
+
+    #[test]
+    fn test_ellipse_closes_after_a_period() {
+        let pos = Vec3 {
+            x: 7e6,
+            y: 0.,
+            z: 0.,
+        };
+        let vel = Vec3 {
+            x: 0.,
+            y: 8500.,
+            z: 1000.,
+        };
+        let alpha = 2. / 7e6 - (&vel * &vel) / MU;
+        let period = TAU / (MU * alpha.powi(3)).sqrt();
+
+        let (end, end_vel) = propagate(MU, &pos, &vel, 3. * period);
+        assert!(end.equal_to(&pos, 1e-3), "{:?}", end);
+        assert!(end_vel.equal_to(&vel, 1e-6), "{:?}", end_vel);
+
+        // Half a period takes the body to apoapsis on the other side.
+        let (apoapsis, apoapsis_vel) = propagate(MU, &pos, &vel, period / 2.);
+        assert!(apoapsis.x < 0., "{:?}", apoapsis);
+        assert!(
+            (apoapsis.normalize().distance - (2. / alpha - 7e6)).abs() < 1e-3
+        );
+        let periapsis = periapsis(MU, &apoapsis, &apoapsis_vel);
+        assert!((periapsis - 7e6).abs() < 1e-3, "{periapsis}");
+    }
+
+    #[test]
+    fn test_steps_add_up_on_a_hyperbola() {
+        let pos = Vec3 {
+            x: 7e6,
+            y: 0.,
+            z: 0.,
+        };
+        let vel = Vec3 {
+            x: 1000.,
+            y: 12_000.,
+            z: 0.,
+        };
+        let (once, _) = propagate(MU, &pos, &vel, 20_000.);
+        let (mut split, mut split_vel) = (pos.clone(), vel.clone());
+        for _ in 0..20 {
+            (split, split_vel) = propagate(MU, &split, &split_vel, 1000.);
+        }
+        assert!(once.equal_to(&split, 1e-3), "{:?} {:?}", once, split);
+
+        let error = (energy(&split, &split_vel) - energy(&pos, &vel)).abs();
+        assert!(error < 1e-6 * energy(&pos, &vel).abs(), "{error}");
+    }
+}
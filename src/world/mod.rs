@@ -1,7 +1,20 @@
 #[allow(clippy::module_inception)]
 mod world;
+pub mod atmosphere;
+pub mod attitude;
 pub mod celestials;
+pub mod clock;
+pub mod collision;
 pub mod config;
+pub mod cr3bp;
+pub mod diagnostics;
+pub mod gravity;
+pub mod harmonics;
+pub mod kepler;
+pub mod particles;
+pub mod radiation;
+pub mod rotation;
+pub mod soi;
 pub mod spaceship;
 
-pub use world::{Body, World};
+pub use world::{Body, State, World};
@@ -0,0 +1,383 @@
+use super::celestials::{Celestial, Celestials};
+use super::World;
+use crate::utils::{Vec3, G};
+use rand::Rng;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::TAU;
+
+/// Fewest substeps a particle takes per orbit, see [`substeps`].
+const SUBSTEPS_PER_ORBIT: f64 = 100.;
+
+/// Massless test particles, such as fragments or ring material. They feel
+/// the point mass pull of the celestials and nothing else, pull on nothing
+/// and vanish when they hit a celestial. Coordinates are kept in separate
+/// arrays, so that tens of thousands of particles stay cheap to step, copy
+/// and draw.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Particles {
+    x: Vec<f64>,
+    y: Vec<f64>,
+    z: Vec<f64>,
+    vx: Vec<f64>,
+    vy: Vec<f64>,
+    vz: Vec<f64>,
+}
+
+impl Particles {
+    pub fn len(&self) -> usize {
+        self.x.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.x.is_empty()
+    }
+
+    pub fn push(&mut self, pos: Vec3, vel: Vec3) {
+        self.x.push(pos.x);
+        self.y.push(pos.y);
+        self.z.push(pos.z);
+        self.vx.push(vel.x);
+        self.vy.push(vel.y);
+        self.vz.push(vel.z);
+    }
+
+    pub fn pos(&self, i: usize) -> Vec3 {
+        Vec3 {
+            x: self.x[i],
+            y: self.y[i],
+            z: self.z[i],
+        }
+    }
+
+    pub fn vel(&self, i: usize) -> Vec3 {
+        Vec3 {
+            x: self.vx[i],
+            y: self.vy[i],
+            z: self.vz[i],
+        }
+    }
+
+    pub fn set_state(&mut self, i: usize, pos: Vec3, vel: Vec3) {
+        (self.x[i], self.y[i], self.z[i]) = (pos.x, pos.y, pos.z);
+        (self.vx[i], self.vy[i], self.vz[i]) = (vel.x, vel.y, vel.z);
+    }
+
+    /// `count` particles on circular orbits around `center` in the plane
+    /// of its equator, or of the x and y axes when it does not rotate,
+    /// spread evenly over the area between `inner` and `outer` radius.
+    pub fn ring(
+        &mut self,
+        center: &Celestial,
+        inner: f64,
+        outer: f64,
+        count: usize,
+        rng: &mut impl Rng,
+    ) {
+        let axes = center.rotation().map(|rotation| rotation.axes(0.));
+        let [x, y, _] = axes.unwrap_or([
+            Vec3 {
+                x: 1.,
+                y: 0.,
+                z: 0.,
+            },
+            Vec3 {
+                x: 0.,
+                y: 1.,
+                z: 0.,
+            },
+            Vec3::default(),
+        ]);
+        let mu = G * center.mass();
+        for _ in 0..count {
+            let radius = rng.gen_range(inner.powi(2)..outer.powi(2)).sqrt();
+            let angle = rng.gen_range(0. ..TAU);
+            let speed = (mu / radius).sqrt();
+            let (sin, cos) = angle.sin_cos();
+            self.push(
+                center.pos() + &(&x * (radius * cos) + &(&y * (radius * sin))),
+                center.vel() + &(&x * (-speed * sin) + &(&y * (speed * cos))),
+            );
+        }
+    }
+
+    /// `count` fragments of a breakup at `pos`, flying apart from `vel`
+    /// in random directions at up to `speed`.
+    pub fn debris(
+        &mut self,
+        pos: &Vec3,
+        vel: &Vec3,
+        speed: f64,
+        count: usize,
+        rng: &mut impl Rng,
+    ) {
+        for _ in 0..count {
+            self.push(pos.clone(), vel.clone() + &(ball(rng) * speed));
+        }
+    }
+
+    /// `count` particles spread evenly through a ball of `radius` around
+    /// `pos`, moving with `vel` give or take up to `speed`.
+    pub fn swarm(
+        &mut self,
+        pos: &Vec3,
+        vel: &Vec3,
+        radius: f64,
+        speed: f64,
+        count: usize,
+        rng: &mut impl Rng,
+    ) {
+        for _ in 0..count {
+            self.push(
+                pos.clone() + &(ball(rng) * radius),
+                vel.clone() + &(ball(rng) * speed),
+            );
+        }
+    }
+
+    /// Steps every particle through `delta_t` kick-drift-kick, in as many
+    /// equal substeps as `substeps` asks for at its position, spread over
+    /// the thread pool. `acceleration` is handed the fraction of `delta_t`
+    /// that has passed and the particle's position and velocity.
+    fn step(
+        &mut self,
+        delta_t: f64,
+        substeps: impl Fn(&Vec3) -> usize + Sync,
+        acceleration: impl Fn(f64, &Vec3, &Vec3) -> Vec3 + Sync,
+    ) {
+        let states: Vec<(Vec3, Vec3)> = (0..self.len())
+            .into_par_iter()
+            .map(|i| {
+                let (mut pos, mut vel) = (self.pos(i), self.vel(i));
+                let substeps = substeps(&pos);
+                let h = delta_t / substeps as f64;
+                let mut a = acceleration(0., &pos, &vel);
+                for k in 1..=substeps {
+                    vel += &a * (h / 2.);
+                    pos += &vel * h;
+                    a = acceleration(k as f64 / substeps as f64, &pos, &vel);
+                    vel += &a * (h / 2.);
+                }
+                (pos, vel)
+            })
+            .collect();
+        for (i, (pos, vel)) in states.into_iter().enumerate() {
+            self.set_state(i, pos, vel);
+        }
+    }
+
+    /// Keeps the particles whose position passes `keep`.
+    fn retain(&mut self, keep: impl Fn(&Vec3) -> bool) {
+        let kept: Vec<bool> =
+            (0..self.len()).map(|i| keep(&self.pos(i))).collect();
+        for values in [
+            &mut self.x,
+            &mut self.y,
+            &mut self.z,
+            &mut self.vx,
+            &mut self.vy,
+            &mut self.vz,
+        ] {
+            let mut kept = kept.iter();
+            values.retain(|_| *kept.next().unwrap());
+        }
+    }
+}
+
+/// Uniformly distributed point in the unit ball.
+fn ball(rng: &mut impl Rng) -> Vec3 {
+    loop {
+        let point = Vec3 {
+            x: rng.gen_range(-1. ..1.),
+            y: rng.gen_range(-1. ..1.),
+            z: rng.gen_range(-1. ..1.),
+        };
+        if point.normalize().distance_sq <= 1. {
+            return point;
+        }
+    }
+}
+
+/// Substeps that take a particle at `pos` no further than a hundredth of
+/// the way round its orbit in `delta_t`, given the position and the
+/// gravitational parameter of every attractor. The orbit is taken to be the
+/// circle around the attractor that pulls hardest.
+fn substeps(attractors: &[(Vec3, f64)], pos: &Vec3, delta_t: f64) -> usize {
+    let nearest = attractors
+        .iter()
+        .map(|(center, mu)| ((pos - center).normalize().distance, *mu))
+        .max_by(|a, b| (a.1 / a.0.powi(2)).total_cmp(&(b.1 / b.0.powi(2))));
+    let Some((distance, mu)) = nearest else {
+        return 1;
+    };
+    let period = TAU * (distance.powi(3) / mu).sqrt();
+    (delta_t.abs() / (period / SUBSTEPS_PER_ORBIT))
+        .ceil()
+        .max(1.) as usize
+}
+
+impl World {
+    /// Steps the particles through `delta_t`, given the celestials as they
+    /// were `before` the step and as they are now after it. Meanwhile the
+    /// celestials are taken to move in straight lines, which holds as long
+    /// as the step is short next to their own orbits. Particles that end up
+    /// inside a celestial are removed.
+    pub fn step_particles(&mut self, before: &Celestials, delta_t: f64) {
+        let World {
+            celestials,
+            particles,
+            cr3bp,
+            ..
+        } = self;
+        match cr3bp.as_ref() {
+            Some(cr3bp) => {
+                let (primary, secondary) = cr3bp.primaries();
+                let attractors =
+                    [(primary, cr3bp.mu.0), (secondary, cr3bp.mu.1)];
+                particles.step(
+                    delta_t,
+                    |pos| substeps(&attractors, pos, delta_t),
+                    |_, pos, vel| cr3bp.acceleration(pos, vel),
+                );
+            }
+            None => {
+                // Start, end and gravitational parameter of every celestial.
+                let paths: Vec<(Vec3, Vec3, f64)> = celestials
+                    .values()
+                    .map(|c| {
+                        let start = before.get_ref(&c.name()).unwrap_or(c);
+                        (start.pos(), c.pos(), G * c.mass())
+                    })
+                    .collect();
+                let attractors: Vec<(Vec3, f64)> = paths
+                    .iter()
+                    .map(|(start, _, mu)| (start.clone(), *mu))
+                    .collect();
+                particles.step(
+                    delta_t,
+                    |pos| substeps(&attractors, pos, delta_t),
+                    |fraction, pos, _| {
+                        let mut acceleration = Vec3::default();
+                        for (start, end, mu) in &paths {
+                            let center = start + ((end - start) * fraction);
+                            let offset = &center - pos;
+                            let distance_sq = offset.normalize().distance_sq;
+                            if distance_sq > 1. {
+                                acceleration += offset
+                                    * (mu / (distance_sq * distance_sq.sqrt()));
+                            }
+                        }
+                        acceleration
+                    },
+                );
+            }
+        }
+
+        particles.retain(|pos| {
+            celestials.values().all(|c| {
+                (pos - &c.pos()).normalize().distance_sq > c.rad().powi(2)
+            })
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::config;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    /// The Earth alone.
+    fn world() -> World {
+        let mut celestials = Celestials::new();
+        celestials.add(config::earth());
+        World::new(celestials, Default::default())
+    }
+
+    /// Lets the Earth drift along and steps the particles after it.
+    fn step(world: &mut World, delta_t: f64) {
+        let before = world.celestials.clone();
+        for celestial in world.celestials.values_mut() {
+            celestial.drift(delta_t);
+        }
+        world.step_particles(&before, delta_t);
+    }
+
+    #[test]
+    fn test_ring_stays_in_orbit() {
+        let earth = config::earth();
+        let mut world = world();
+        let mut rng = StdRng::seed_from_u64(0);
+        world.particles.ring(&earth, 2e7, 3e7, 1000, &mut rng);
+        assert_eq!(world.particles.len(), 1000);
+
+        let distances = |world: &World| -> Vec<f64> {
+            let earth = world.celestials.get_ref("Earth").unwrap();
+            (0..world.particles.len())
+                .map(|i| {
+                    (world.particles.pos(i) - &earth.pos()).normalize().distance
+                })
+                .collect()
+        };
+        let start = distances(&world);
+        for _ in 0..1000 {
+            step(&mut world, 10.);
+        }
+        for (end, start) in distances(&world).iter().zip(&start) {
+            assert!((end - start).abs() < 1e-3 * start, "{end} {start}");
+        }
+    }
+
+    #[test]
+    fn test_ring_survives_long_steps() {
+        let earth = config::earth();
+        let mut world = world();
+        let mut rng = StdRng::seed_from_u64(0);
+        // Orbits of 8 to 14 hours, stepped a day at a time.
+        world.particles.ring(&earth, 2e7, 3e7, 100, &mut rng);
+
+        let distances = |world: &World| -> Vec<f64> {
+            let earth = world.celestials.get_ref("Earth").unwrap();
+            (0..world.particles.len())
+                .map(|i| {
+                    (world.particles.pos(i) - &earth.pos()).normalize().distance
+                })
+                .collect()
+        };
+        let start = distances(&world);
+        for _ in 0..10 {
+            step(&mut world, 86_400.);
+        }
+        // A single kick of a day would throw them out of the ring.
+        assert_eq!(world.particles.len(), 100);
+        for (end, start) in distances(&world).iter().zip(&start) {
+            assert!((end - start).abs() < 1e-2 * start, "{end} {start}");
+        }
+    }
+
+    #[test]
+    fn test_debris_falling_in_is_removed() {
+        let earth = config::earth();
+        let mut world = world();
+        let mut rng = StdRng::seed_from_u64(0);
+        // Barely moving 100 km up, so everything falls within ten minutes.
+        let pos = earth.pos()
+            + &Vec3 {
+                x: earth.rad() + 1e5,
+                y: 0.,
+                z: 0.,
+            };
+        world
+            .particles
+            .debris(&pos, &earth.vel(), 10., 500, &mut rng);
+        world
+            .particles
+            .swarm(&pos, &earth.vel(), 1e3, 10., 500, &mut rng);
+
+        for _ in 0..600 {
+            step(&mut world, 1.);
+        }
+        assert!(world.particles.is_empty());
+    }
+}
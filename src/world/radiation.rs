@@ -0,0 +1,98 @@
+use super::celestials::Celestial;
+use crate::utils::{Vec3, C};
+use std::f64::consts::PI;
+
+/// Radiation pressure of `source` on a body at `pos` with `area_per_mass`
+/// facing it, in m²/kg, and `reflectivity` between 0 for a black and 1 for
+/// a mirror surface. It is zero while any of `occulters` covers part of the
+/// source's disk, in its umbra as well as its penumbra.
+pub fn acceleration<'a>(
+    source: &Celestial,
+    luminosity: f64,
+    pos: &Vec3,
+    area_per_mass: f64,
+    reflectivity: f64,
+    occulters: impl Iterator<Item = &'a Celestial>,
+) -> Vec3 {
+    let sunlight = (pos - &source.pos()).normalize();
+    for occulter in occulters {
+        if in_shadow(source, occulter, pos) {
+            return Vec3::default();
+        }
+    }
+
+    let pressure = luminosity / (4. * PI * sunlight.distance_sq * C);
+    sunlight.unit_direction * (pressure * (1. + reflectivity) * area_per_mass)
+}
+
+/// Whether `occulter` hides part of the disk of `source` seen from `pos`.
+fn in_shadow(source: &Celestial, occulter: &Celestial, pos: &Vec3) -> bool {
+    let to_source = (source.pos() - pos).normalize();
+    let to_occulter = (occulter.pos() - pos).normalize();
+    if to_occulter.distance >= to_source.distance
+        || to_occulter.distance <= occulter.rad()
+    {
+        return false;
+    }
+
+    let source_radius = (source.rad() / to_source.distance).asin();
+    let occulter_radius = (occulter.rad() / to_occulter.distance).asin();
+    let separation = (&to_source.unit_direction * &to_occulter.unit_direction)
+        .clamp(-1., 1.)
+        .acos();
+    separation < source_radius + occulter_radius
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const AU: f64 = 1.495_978_707e11;
+
+    fn body(name: &str, x: f64, rad: f64) -> Celestial {
+        let pos = Vec3 { x, y: 0., z: 0. };
+        Celestial::new(name.to_string(), 1., pos, Vec3::default(), rad)
+    }
+
+    #[test]
+    fn test_pressure_and_shadow() {
+        let sun = body("Sun", 0., 6.9634e8);
+        let earth = body("Earth", AU, 6.371e6);
+        let luminosity = 3.828e26;
+
+        let sunlit = |pos: Vec3| {
+            acceleration(&sun, luminosity, &pos, 1., 0., [&earth].into_iter())
+        };
+
+        // About 4.5 μN/m² for a black surface at 1 AU.
+        let day = sunlit(Vec3 {
+            x: AU - 1e7,
+            y: 0.,
+            z: 0.,
+        });
+        assert!((day.x - 4.541e-6).abs() < 1e-9, "{:?}", day);
+
+        let umbra = sunlit(Vec3 {
+            x: AU + 1e7,
+            y: 0.,
+            z: 0.,
+        });
+        assert_eq!(umbra.x, 0.);
+
+        // 1e6 km behind the Earth the umbra is 1800 km and the penumbra
+        // 11 000 km wide.
+        let penumbra = sunlit(Vec3 {
+            x: AU + 1e9,
+            y: 8e6,
+            z: 0.,
+        });
+        assert_eq!(penumbra.x, 0.);
+
+        let beside = sunlit(Vec3 {
+            x: AU + 1e7,
+            y: 1e7,
+            z: 0.,
+        });
+        assert!(beside.x > 0.);
+    }
+}
@@ -0,0 +1,253 @@
+use super::celestials::Celestial;
+use crate::utils::Vec3;
+use serde::{Deserialize, Serialize};
+use std::f64::consts::TAU;
+use std::fmt;
+
+/// Uniform spin of a celestial about a fixed axis, in the style of the IAU
+/// rotation elements.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Rotation {
+    /// Unit vector along the north pole in inertial coordinates.
+    pub pole: Vec3,
+    /// Sidereal period in s.
+    pub period: f64,
+    /// Angle in rad of the prime meridian at J2000.0, east from the node
+    /// where the equator crosses the inertial x-y plane going north, or from
+    /// the x axis when they coincide.
+    pub meridian: f64,
+}
+
+impl Rotation {
+    /// In rad/s.
+    pub fn angular_velocity(&self) -> Vec3 {
+        &self.pole * (TAU / self.period)
+    }
+
+    /// Body-fixed axes in inertial coordinates at `tt` seconds since
+    /// J2000.0: x through the prime meridian on the equator, z along the
+    /// pole and y completing them to the east.
+    pub fn axes(&self, tt: f64) -> [Vec3; 3] {
+        let z = Vec3 {
+            x: 0.,
+            y: 0.,
+            z: 1.,
+        };
+        let node = z.cross(&self.pole).normalize();
+        let node = if node.distance > 1e-12 {
+            node.unit_direction
+        } else {
+            Vec3 {
+                x: 1.,
+                y: 0.,
+                z: 0.,
+            }
+        };
+        let angle = self.meridian + TAU * tt / self.period;
+        let x = &node * angle.cos() + &(self.pole.cross(&node) * angle.sin());
+        let y = self.pole.cross(&x);
+        [x, y, self.pole.clone()]
+    }
+}
+
+/// Oblate spheroid that geodetic coordinates refer to.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Ellipsoid {
+    /// Equatorial radius in m.
+    pub radius: f64,
+    pub flattening: f64,
+}
+
+/// Position over the reference ellipsoid of a celestial.
+pub struct Geodetic {
+    /// In rad, north positive.
+    pub latitude: f64,
+    /// In rad east of the prime meridian, from -π to π.
+    pub longitude: f64,
+    /// Height above the ellipsoid along its normal in m.
+    pub altitude: f64,
+}
+
+impl fmt::Display for Geodetic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "lat {:.3} lon {:.3} alt {:.1} km",
+            self.latitude.to_degrees(),
+            self.longitude.to_degrees(),
+            self.altitude / 1000.
+        )
+    }
+}
+
+impl Celestial {
+    /// Body-fixed axes at `tt`, the inertial ones without a rotation.
+    fn axes(&self, tt: f64) -> [Vec3; 3] {
+        match self.rotation() {
+            Some(rotation) => rotation.axes(tt),
+            None => [
+                Vec3 {
+                    x: 1.,
+                    y: 0.,
+                    z: 0.,
+                },
+                Vec3 {
+                    x: 0.,
+                    y: 1.,
+                    z: 0.,
+                },
+                Vec3 {
+                    x: 0.,
+                    y: 0.,
+                    z: 1.,
+                },
+            ],
+        }
+    }
+
+    fn spin(&self) -> Vec3 {
+        self.rotation()
+            .map_or(Vec3::default(), |rotation| rotation.angular_velocity())
+    }
+
+    /// Inertial `pos` and `vel` at `tt` seconds since J2000.0 as seen in
+    /// the celestial's rotating body-fixed frame centered on it, like ECEF
+    /// coordinates for the Earth.
+    pub fn to_body_fixed(
+        &self,
+        pos: &Vec3,
+        vel: &Vec3,
+        tt: f64,
+    ) -> (Vec3, Vec3) {
+        let offset = pos - &self.pos();
+        let vel = vel - &self.vel() - &self.spin().cross(&offset);
+        let [x, y, z] = self.axes(tt);
+        let project = |v: &Vec3| Vec3 {
+            x: v * &x,
+            y: v * &y,
+            z: v * &z,
+        };
+        (project(&offset), project(&vel))
+    }
+
+    /// Inverse of [`Self::to_body_fixed`].
+    pub fn to_inertial(&self, pos: &Vec3, vel: &Vec3, tt: f64) -> (Vec3, Vec3) {
+        let [x, y, z] = self.axes(tt);
+        let turn = |v: &Vec3| &x * v.x + &(&y * v.y) + &(&z * v.z);
+        let offset = turn(pos);
+        let vel = turn(vel) + &self.spin().cross(&offset);
+        (self.pos() + &offset, self.vel() + &vel)
+    }
+
+    /// Geodetic coordinates of the inertial `pos` at `tt` seconds since
+    /// J2000.0, over a sphere of the celestial's radius when it has no
+    /// ellipsoid.
+    pub fn geodetic(&self, pos: &Vec3, tt: f64) -> Geodetic {
+        let (fixed, _) = self.to_body_fixed(pos, &Vec3::default(), tt);
+        let Ellipsoid { radius, flattening } =
+            self.ellipsoid().cloned().unwrap_or(Ellipsoid {
+                radius: self.rad(),
+                flattening: 0.,
+            });
+        let e2 = flattening * (2. - flattening);
+        let p = fixed.x.hypot(fixed.y);
+
+        // Fixed point iteration, which converges to well below a millimetre
+        // within a few rounds anywhere outside the core.
+        let mut latitude = fixed.z.atan2(p * (1. - e2));
+        let mut normal = radius;
+        for _ in 0..10 {
+            normal = radius / (1. - e2 * latitude.sin().powi(2)).sqrt();
+            latitude = (fixed.z + e2 * normal * latitude.sin()).atan2(p);
+        }
+        Geodetic {
+            latitude,
+            longitude: fixed.y.atan2(fixed.x),
+            altitude: p * latitude.cos()
+                + (fixed.z + e2 * normal * latitude.sin()) * latitude.sin()
+                - normal,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::config;
+
+    #[test]
+    fn test_surface_point_turns_with_the_earth() {
+        let earth = config::earth();
+        let rotation = earth.rotation().unwrap();
+        let surface = Vec3 {
+            x: 0.,
+            y: earth.rad(),
+            z: 0.,
+        };
+        let (pos, vel) = earth.to_inertial(&surface, &Vec3::default(), 1000.);
+        let speed = (&vel - &earth.vel()).normalize().distance;
+        assert!((speed - earth.rad() * TAU / rotation.period).abs() < 1e-9);
+
+        let (fixed, fixed_vel) = earth.to_body_fixed(&pos, &vel, 1000.);
+        // Far from the origin positions round to some 30 µm.
+        assert!(fixed.equal_to(&surface, 1e-4), "{fixed:?}");
+        assert!(fixed_vel.equal_to(&Vec3::default(), 1e-6));
+        // A sidereal day later it is back where it was.
+        let (later, _) = earth.to_inertial(
+            &surface,
+            &Vec3::default(),
+            1000. + rotation.period,
+        );
+        assert!(later.equal_to(&pos, 1e-3));
+    }
+
+    #[test]
+    fn test_greenwich_at_j2000() {
+        let earth = config::earth();
+        let rotation = earth.rotation().unwrap();
+        let [greenwich, _, _] = rotation.axes(0.);
+        let equinox = Vec3 {
+            x: 1.,
+            y: 0.,
+            z: 0.,
+        };
+        let east = rotation.pole.cross(&equinox);
+        let right_ascension = (&greenwich * &east)
+            .atan2(&greenwich * &equinox)
+            .to_degrees()
+            .rem_euclid(360.);
+        // GMST at J2000.0 TT, with UT1 64 s behind TT.
+        assert!((right_ascension - 280.19).abs() < 0.1, "{right_ascension}");
+    }
+
+    #[test]
+    fn test_geodetic_round_trip() {
+        let earth = config::earth();
+        let Ellipsoid { radius, flattening } =
+            earth.ellipsoid().cloned().unwrap();
+        let e2 = flattening * (2. - flattening);
+        for (latitude, longitude, altitude) in [
+            (0., 0., 0.),
+            (51.48_f64, -0.0015_f64, 45.),
+            (-33.9, 151.2, 400_000.),
+            (89.99, 10., 10_000.),
+        ] {
+            let (phi, lambda) = (latitude.to_radians(), longitude.to_radians());
+            let normal = radius / (1. - e2 * phi.sin().powi(2)).sqrt();
+            let fixed = Vec3 {
+                x: (normal + altitude) * phi.cos() * lambda.cos(),
+                y: (normal + altitude) * phi.cos() * lambda.sin(),
+                z: (normal * (1. - e2) + altitude) * phi.sin(),
+            };
+            let (pos, _) = earth.to_inertial(&fixed, &Vec3::default(), 5e8);
+
+            let geodetic = earth.geodetic(&pos, 5e8);
+            assert!((geodetic.latitude - phi).abs() < 1e-9, "{geodetic}");
+            // Near the pole longitude is as loose as the distance to the axis
+            // is short, so it is checked as a distance along the parallel.
+            let east = (geodetic.longitude - lambda).abs() * phi.cos() * radius;
+            assert!(east < 1e-3, "{geodetic}");
+            assert!((geodetic.altitude - altitude).abs() < 1e-3, "{geodetic}");
+        }
+    }
+}
@@ -0,0 +1,158 @@
+use super::celestials::{Celestial, Celestials};
+use super::World;
+use crate::utils::Vec3;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Newest switches kept on [`World::soi_switches`].
+const MAX_EVENTS: usize = 8;
+
+/// Laplace sphere of influence `a (m / M)^(2/5)` of a celestial of mass `m`
+/// at distance `a` from its parent of mass `M`.
+#[derive(Clone, Debug)]
+pub struct Sphere {
+    /// The heavier celestial with the smallest sphere around this one, none
+    /// for the heaviest celestial.
+    pub parent: Option<String>,
+    /// Infinite for the heaviest celestial.
+    pub radius: f64,
+}
+
+/// A spaceship crossing into the sphere of influence of another celestial.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SoiSwitch {
+    pub time: f64,
+    pub spaceship: String,
+    pub from: String,
+    pub to: String,
+}
+
+impl fmt::Display for SoiSwitch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:.0} s: {} left {} for {}",
+            self.time, self.spaceship, self.from, self.to
+        )
+    }
+}
+
+impl Celestials {
+    /// Spheres of influence of all celestials by name.
+    pub fn spheres_of_influence(&self) -> HashMap<String, Sphere> {
+        let mut celestials: Vec<&Celestial> = self.values().collect();
+        celestials.sort_by(|a, b| b.mass().total_cmp(&a.mass()));
+
+        let mut spheres = HashMap::new();
+        for (i, celestial) in celestials.iter().enumerate() {
+            let pos = celestial.pos();
+            let sphere = match smallest(&celestials[..i], &spheres, &pos) {
+                Some(parent) => Sphere {
+                    parent: Some(parent.name()),
+                    radius: (pos - &parent.pos()).normalize().distance
+                        * (celestial.mass() / parent.mass()).powf(0.4),
+                },
+                None => Sphere {
+                    parent: None,
+                    radius: f64::INFINITY,
+                },
+            };
+            spheres.insert(celestial.name(), sphere);
+        }
+        spheres
+    }
+
+    /// The celestial with the smallest sphere of influence around `pos`.
+    pub fn dominant(
+        &self,
+        spheres: &HashMap<String, Sphere>,
+        pos: &Vec3,
+    ) -> Option<String> {
+        let celestials: Vec<&Celestial> = self.values().collect();
+        smallest(&celestials, spheres, pos).map(|c| c.name())
+    }
+}
+
+fn smallest<'a>(
+    celestials: &[&'a Celestial],
+    spheres: &HashMap<String, Sphere>,
+    pos: &Vec3,
+) -> Option<&'a Celestial> {
+    celestials
+        .iter()
+        .filter_map(|c| {
+            let radius = spheres.get(&c.name())?.radius;
+            let inside = (pos - &c.pos()).normalize().distance < radius;
+            inside.then_some((*c, radius))
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .map(|(c, _)| c)
+}
+
+impl World {
+    /// Records in `soi_switches` that `spaceship` moved from the sphere of
+    /// influence of `from` into that of `to` at `time`.
+    pub fn record_soi_switch(
+        &mut self,
+        time: f64,
+        spaceship: &str,
+        from: &str,
+        to: &str,
+    ) {
+        if self.soi_switches.len() == MAX_EVENTS {
+            self.soi_switches.remove(0);
+        }
+        self.soi_switches.push(SoiSwitch {
+            time,
+            spaceship: spaceship.to_string(),
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::config;
+
+    #[test]
+    fn test_solar_hierarchy() {
+        let celestials = config::new_solar();
+        let spheres = celestials.spheres_of_influence();
+
+        assert!(spheres["Sun"].parent.is_none());
+        assert_eq!(spheres["Earth"].parent.as_deref(), Some("Sun"));
+        assert_eq!(spheres["Moon"].parent.as_deref(), Some("Earth"));
+        // About 940 000 km and 70 000 km at the scenario's distances.
+        let radius = |name: &str| spheres[name].radius;
+        assert!((radius("Earth") - 9.40e8).abs() < 1e7, "{spheres:?}");
+        assert!((radius("Moon") - 6.98e7).abs() < 1e6, "{spheres:?}");
+
+        let earth = config::earth();
+        let low_orbit = &earth.pos()
+            + Vec3 {
+                x: 7e6,
+                y: 0.,
+                z: 0.,
+            };
+        let far_away = &earth.pos()
+            + Vec3 {
+                x: 0.,
+                y: 2e9,
+                z: 0.,
+            };
+        let near_moon = config::moon().pos()
+            + &Vec3 {
+                x: 1e7,
+                y: 0.,
+                z: 0.,
+            };
+        for (pos, name) in
+            [(low_orbit, "Earth"), (far_away, "Sun"), (near_moon, "Moon")]
+        {
+            assert_eq!(celestials.dominant(&spheres, &pos).unwrap(), name);
+        }
+    }
+}
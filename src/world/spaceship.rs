@@ -1,23 +1,65 @@
+use super::attitude::Attitude;
 use crate::Vec3;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Spaceship {
     name: String,
-    _mass: f64,
+    mass: f64,
     pos: Vec3,
     vel: Vec3,
+    /// Cross-section facing the airflow, in m².
+    drag_area: f64,
+    drag_coefficient: f64,
+    /// Cross-section facing the Sun, in m².
+    radiation_area: f64,
+    /// Share of sunlight reflected, from 0 for black to 1 for a mirror.
+    reflectivity: f64,
+    attitude: Attitude,
 }
 
 impl Spaceship {
-    pub fn new(name: String, _mass: f64, pos: Vec3, vel: Vec3) -> Self {
+    pub fn new(name: String, mass: f64, pos: Vec3, vel: Vec3) -> Self {
         Self {
             name,
-            _mass,
+            mass,
             pos,
             vel,
+            drag_area: 0.,
+            drag_coefficient: 2.2,
+            radiation_area: 0.,
+            reflectivity: 0.,
+            // A uniform ball of 1 m radius until told otherwise.
+            attitude: Attitude::new([
+                [0.4 * mass, 0., 0.],
+                [0., 0.4 * mass, 0.],
+                [0., 0., 0.4 * mass],
+            ]),
         }
     }
 
+    pub fn with_drag(mut self, area: f64, coefficient: f64) -> Self {
+        self.drag_area = area;
+        self.drag_coefficient = coefficient;
+        self
+    }
+
+    pub fn with_radiation_pressure(
+        mut self,
+        area: f64,
+        reflectivity: f64,
+    ) -> Self {
+        self.radiation_area = area;
+        self.reflectivity = reflectivity;
+        self
+    }
+
+    /// Principal moments of inertia about the body axes in kg m².
+    pub fn with_inertia(mut self, x: f64, y: f64, z: f64) -> Self {
+        self.attitude.inertia = [[x, 0., 0.], [0., y, 0.], [0., 0., z]];
+        self
+    }
+
     pub fn name(&self) -> String {
         self.name.clone()
     }
@@ -26,15 +68,54 @@ impl Spaceship {
         self.pos.clone()
     }
 
-    pub fn _mass(&self) -> f64 {
-        self._mass
+    pub fn vel(&self) -> Vec3 {
+        self.vel.clone()
+    }
+
+    pub fn mass(&self) -> f64 {
+        self.mass
+    }
+
+    /// `m / (C_d A)` in kg/m², infinite for a ship without a drag area.
+    pub fn ballistic_coefficient(&self) -> f64 {
+        self.mass / (self.drag_coefficient * self.drag_area)
+    }
+
+    pub fn radiation_area(&self) -> f64 {
+        self.radiation_area
     }
 
-    pub fn apply_gravity(&mut self, acceleration: Vec3, delta_t: f64) {
+    pub fn reflectivity(&self) -> f64 {
+        self.reflectivity
+    }
+
+    pub fn attitude(&self) -> &Attitude {
+        &self.attitude
+    }
+
+    /// Torque in body coordinates in N m, applied until it is changed.
+    pub fn set_torque(&mut self, torque: Vec3) {
+        self.attitude.torque = torque;
+    }
+
+    /// Turns the spaceship through `delta_t` under its torque.
+    pub fn rotate(&mut self, delta_t: f64) {
+        self.attitude.step(delta_t);
+    }
+
+    pub fn kick(&mut self, acceleration: Vec3, delta_t: f64) {
         self.vel += acceleration * delta_t;
+    }
+
+    pub fn drift(&mut self, delta_t: f64) {
         self.pos += &self.vel * delta_t;
     }
 
+    pub fn set_state(&mut self, pos: Vec3, vel: Vec3) {
+        self.pos = pos;
+        self.vel = vel;
+    }
+
     pub fn speedup(&mut self) {
         self.vel.y *= 2.;
     }
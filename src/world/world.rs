@@ -1,7 +1,31 @@
 use super::celestials::Celestials;
+use super::clock::Clock;
+use super::collision::{Collision, CollisionPolicy};
+use super::cr3bp::Cr3bp;
+use super::diagnostics::{Diagnostics, Drift};
+use super::gravity::{Gravity, Octree};
+use super::particles::Particles;
+use super::soi::SoiSwitch;
 use super::spaceship::Spaceship;
 use crate::{Celestial, Vec3};
-use std::collections::HashMap;
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// Below this many bodies the thread pool costs more than it saves.
+const PARALLEL_THRESHOLD: usize = 64;
+
+fn map<T: Sync>(
+    items: &[T],
+    parallel: bool,
+    f: impl Fn(&T) -> Vec3 + Send + Sync,
+) -> Vec<Vec3> {
+    if parallel {
+        items.par_iter().map(f).collect()
+    } else {
+        items.iter().map(f).collect()
+    }
+}
 
 pub enum Body {
     Celestial(Celestial),
@@ -16,6 +40,13 @@ impl Body {
         }
     }
 
+    pub fn vel(&self) -> Vec3 {
+        match self {
+            Body::Celestial(c) => c.vel(),
+            Body::Spaceship(ss) => ss.vel(),
+        }
+    }
+
     pub fn _name(&self) -> String {
         match self {
             Body::Celestial(c) => c.name(),
@@ -36,22 +67,87 @@ impl From<Celestial> for Body {
     }
 }
 
+/// Positions and velocities of all bodies, ordered like
+/// [`World::accelerations`].
 #[derive(Clone, Debug)]
+pub struct State {
+    pub pos: Vec<Vec3>,
+    pub vel: Vec<Vec3>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct World {
     pub celestials: Celestials,
-    pub spaceships: HashMap<String, Spaceship>,
+    pub spaceships: BTreeMap<String, Spaceship>,
+    /// Simulated TT seconds since the start.
+    pub time: f64,
+    /// Turns `time` into dates.
+    pub clock: Clock,
+    /// Physics steps per wall-clock second.
     pub true_sim_fps: u32,
+    /// Simulated seconds per wall-clock second.
+    pub true_time_speed: f64,
+    pub delta_t: f64,
+    /// Whether the last tick moved all bodies along Kepler conics instead
+    /// of integrating them.
+    pub on_rails: bool,
+    /// Time of the oldest and the newest state the simulation can jump
+    /// back to.
+    pub timeline: Option<(f64, f64)>,
+    pub gravity: Gravity,
+    pub collision_policy: CollisionPolicy,
+    /// Adds the post-Newtonian correction to the pull of celestials.
+    pub relativity: bool,
+    /// Shift everything into the barycentric frame before the run starts,
+    /// cleared once that is done.
+    pub barycentric: bool,
+    /// Physics steps between updates of `diagnostics`.
+    pub diagnostics_every: u32,
+    pub diagnostics: Option<Diagnostics>,
+    /// Diagnostics at the start of the simulation.
+    pub initial_diagnostics: Option<Diagnostics>,
+    /// Newest collisions, oldest first.
+    pub collisions: Vec<Collision>,
+    /// Pairs of bodies touching under an outcome that stops, reported when
+    /// they first touched and forgotten once they come apart.
+    pub contacts: Vec<(String, String)>,
+    /// Newest sphere of influence switches, oldest first.
+    pub soi_switches: Vec<SoiSwitch>,
+    /// Stepped by the simulation next to the integrator, see
+    /// [`Self::step_particles`].
+    pub particles: Particles,
+    /// Propagate spaceships in the rotating frame of two primaries instead
+    /// of the full N-body field.
+    pub cr3bp: Option<Cr3bp>,
 }
 
 impl World {
     pub fn new(
         celestials: Celestials,
-        spaceships: HashMap<String, Spaceship>,
+        spaceships: BTreeMap<String, Spaceship>,
     ) -> Self {
         Self {
             celestials,
             spaceships,
+            time: 0.,
+            clock: Clock::default(),
             true_sim_fps: 0,
+            true_time_speed: 0.,
+            delta_t: 0.,
+            on_rails: false,
+            timeline: None,
+            gravity: Gravity::Direct,
+            collision_policy: CollisionPolicy::default(),
+            relativity: false,
+            barycentric: false,
+            diagnostics_every: 1,
+            diagnostics: None,
+            initial_diagnostics: None,
+            collisions: Vec::new(),
+            contacts: Vec::new(),
+            soi_switches: Vec::new(),
+            particles: Particles::default(),
+            cr3bp: None,
         }
     }
 
@@ -65,4 +161,226 @@ impl World {
         }
         res
     }
+
+    /// Accelerations of all celestials followed by all spaceships, each in
+    /// the order of their names. Large worlds are spread over a thread
+    /// pool.
+    pub fn accelerations(&self) -> Vec<Vec3> {
+        let bodies = self.celestials.values().count() + self.spaceships.len();
+        self.accelerations_with(bodies >= PARALLEL_THRESHOLD)
+    }
+
+    /// Every body's acceleration is summed up on a single thread in a fixed
+    /// order, so the parallel result is bitwise identical to the serial one.
+    /// With `relativity` every body gets the post-Newtonian correction.
+    /// Spaceships also feel the non-spherical part of celestials' fields,
+    /// atmospheric drag and radiation pressure. In CR3BP mode the primaries
+    /// rest and spaceships only feel the rotating-frame field.
+    fn accelerations_with(&self, parallel: bool) -> Vec<Vec3> {
+        let spaceships: Vec<&Spaceship> = self.spaceships.values().collect();
+        if let Some(cr3bp) = &self.cr3bp {
+            let mut accelerations =
+                vec![Vec3::default(); self.celestials.values().count()];
+            accelerations.extend(map(&spaceships, parallel, |spaceship| {
+                cr3bp.acceleration(&spaceship.pos(), &spaceship.vel())
+            }));
+            return accelerations;
+        }
+
+        let tree = match self.gravity {
+            Gravity::Direct => None,
+            Gravity::BarnesHut { theta } => {
+                let bodies: Vec<(Vec3, f64)> = self
+                    .celestials
+                    .values()
+                    .map(|c| (c.pos(), c.mass()))
+                    .collect();
+                Some((Octree::new(&bodies), theta))
+            }
+        };
+        let gravity = |pos: &Vec3| match &tree {
+            None => self.celestials.get_global_acceleration(pos.clone()),
+            Some((tree, theta)) => tree.acceleration(pos, *theta),
+        };
+
+        let relativity = |pos: &Vec3, vel: &Vec3| {
+            if self.relativity {
+                self.celestials.get_relativistic_acceleration(pos, vel)
+            } else {
+                Vec3::default()
+            }
+        };
+
+        let celestials: Vec<&Celestial> = self.celestials.values().collect();
+
+        let mut accelerations = map(&celestials, parallel, |celestial| {
+            let pos = celestial.pos();
+            gravity(&pos) + &relativity(&pos, &celestial.vel())
+        });
+        accelerations.extend(map(&spaceships, parallel, |spaceship| {
+            let pos = spaceship.pos();
+            gravity(&pos)
+                + &relativity(&pos, &spaceship.vel())
+                + &self.celestials.get_harmonic_acceleration(&pos)
+                + &self.celestials.get_drag_acceleration(spaceship)
+                + &self.celestials.get_radiation_acceleration(spaceship)
+        }));
+        accelerations
+    }
+
+    /// Position and velocity of the barycenter of the celestials.
+    pub fn barycenter(&self) -> (Vec3, Vec3) {
+        let mut mass = 0.;
+        let mut pos = Vec3::default();
+        let mut vel = Vec3::default();
+        for celestial in self.celestials.values() {
+            mass += celestial.mass();
+            pos += celestial.pos() * celestial.mass();
+            vel += celestial.vel() * celestial.mass();
+        }
+        if mass > 0. {
+            pos *= 1. / mass;
+            vel *= 1. / mass;
+        }
+        (pos, vel)
+    }
+
+    /// Moves all bodies and particles so that the barycenter rests at the
+    /// origin.
+    pub fn center_on_barycenter(&mut self) {
+        let (origin, drift) = self.barycenter();
+        for celestial in self.celestials.values_mut() {
+            celestial
+                .set_state(celestial.pos() - &origin, celestial.vel() - &drift);
+        }
+        for spaceship in self.spaceships.values_mut() {
+            spaceship
+                .set_state(spaceship.pos() - &origin, spaceship.vel() - &drift);
+        }
+        for i in 0..self.particles.len() {
+            let (pos, vel) = (self.particles.pos(i), self.particles.vel(i));
+            self.particles.set_state(i, pos - &origin, vel - &drift);
+        }
+    }
+
+    /// Recomputes `diagnostics`, the first time also the initial ones.
+    pub fn update_diagnostics(&mut self) {
+        let diagnostics = Diagnostics::new(self);
+        if self.initial_diagnostics.is_none() {
+            self.initial_diagnostics = Some(diagnostics.clone());
+        }
+        self.diagnostics = Some(diagnostics);
+    }
+
+    /// Drift of the last diagnostics since the start.
+    pub fn conservation_drift(&self) -> Option<Drift> {
+        let start = self.initial_diagnostics.as_ref()?;
+        Some(self.diagnostics.as_ref()?.drift(start))
+    }
+
+    pub fn state(&self) -> State {
+        let celestials = self.celestials.values().map(|c| (c.pos(), c.vel()));
+        let spaceships = self.spaceships.values().map(|s| (s.pos(), s.vel()));
+        let (pos, vel) = celestials.chain(spaceships).unzip();
+        State { pos, vel }
+    }
+
+    pub fn set_state(&mut self, state: &State) {
+        let mut bodies =
+            state.pos.iter().cloned().zip(state.vel.iter().cloned());
+        for (celestial, (pos, vel)) in
+            self.celestials.values_mut().zip(&mut bodies)
+        {
+            celestial.set_state(pos, vel);
+        }
+        for (spaceship, (pos, vel)) in
+            self.spaceships.values_mut().zip(&mut bodies)
+        {
+            spaceship.set_state(pos, vel);
+        }
+    }
+
+    pub fn kick(&mut self, delta_t: f64) {
+        let mut accelerations = self.accelerations().into_iter();
+        for (celestial, a) in
+            self.celestials.values_mut().zip(&mut accelerations)
+        {
+            celestial.kick(a, delta_t);
+        }
+        for (spaceship, a) in
+            self.spaceships.values_mut().zip(&mut accelerations)
+        {
+            spaceship.kick(a, delta_t);
+        }
+    }
+
+    /// Turns every spaceship through `delta_t`, see [`Spaceship::rotate`].
+    pub fn rotate(&mut self, delta_t: f64) {
+        for spaceship in self.spaceships.values_mut() {
+            spaceship.rotate(delta_t);
+        }
+    }
+
+    pub fn drift(&mut self, delta_t: f64) {
+        for celestial in self.celestials.values_mut() {
+            celestial.drift(delta_t);
+        }
+        for spaceship in self.spaceships.values_mut() {
+            spaceship.drift(delta_t);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::world::config;
+
+    #[test]
+    fn test_parallel_is_bitwise_identical() {
+        let mut world = config::belt(500);
+        let iss = config::iss();
+        for i in 0..100 {
+            let offset = Vec3 {
+                x: 0.,
+                y: 0.,
+                z: 1e4 * i as f64,
+            };
+            let spaceship = Spaceship::new(
+                format!("Ship {}", i),
+                iss.mass(),
+                iss.pos() + &offset,
+                iss.vel(),
+            );
+            world.spaceships.insert(spaceship.name(), spaceship);
+        }
+
+        for gravity in [Gravity::Direct, Gravity::BarnesHut { theta: 0.5 }] {
+            world.gravity = gravity;
+            let serial = world.accelerations_with(false);
+            let parallel = world.accelerations_with(true);
+            assert_eq!(serial.len(), parallel.len());
+            for (s, p) in serial.iter().zip(&parallel) {
+                assert_eq!(
+                    [s.x, s.y, s.z].map(f64::to_bits),
+                    [p.x, p.y, p.z].map(f64::to_bits)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_center_on_barycenter() {
+        let mut world = config::solar();
+        let iss = world.spaceships["ISS"].pos()
+            - &world.celestials.get()["Earth"].pos();
+        world.center_on_barycenter();
+
+        let (pos, vel) = world.barycenter();
+        assert!(pos.equal_to(&Vec3::default(), 1e-3));
+        assert!(vel.equal_to(&Vec3::default(), 1e-12));
+        let moved = world.spaceships["ISS"].pos()
+            - &world.celestials.get()["Earth"].pos();
+        assert!(moved.equal_to(&iss, 1e-3));
+    }
 }